@@ -0,0 +1,99 @@
+//! Compares single-threaded and parallel scans of a generated fixture tree.
+//!
+//! Run with `cargo bench --bench scan`. The fixture (500k entries by default,
+//! override with `PLANTER_BENCH_ENTRIES`) is created once under the target
+//! directory and reused by later runs. Timings are taken with a warm page
+//! cache; the first, untimed pass takes care of that.
+
+use planter::Scanner;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+const DEFAULT_ENTRIES: usize = 500_000;
+const TOP_DIRS: usize = 50;
+const SUB_DIRS: usize = 20;
+const LEAF_DIRS: usize = 10;
+const RUNS: usize = 3;
+
+fn main() -> io::Result<()> {
+    let entries = std::env::var("PLANTER_BENCH_ENTRIES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(DEFAULT_ENTRIES);
+    let root = fixture(entries)?;
+
+    // Warm the page cache so every configuration sees the same conditions
+    let tree = Scanner::new(&root).scan()?;
+    println!(
+        "fixture: {} ({} folders, {} files)",
+        root.display(),
+        tree.stats.total_dirs,
+        tree.stats.total_files
+    );
+
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut thread_counts = vec![1, 2, 4, cpus];
+    thread_counts.sort();
+    thread_counts.dedup();
+
+    let baseline = time_scan(&root, 1)?;
+    println!("threads  1: {:>8.1?}", baseline);
+    for threads in thread_counts.into_iter().filter(|&n| n > 1) {
+        let elapsed = time_scan(&root, threads)?;
+        println!(
+            "threads {:>2}: {:>8.1?}  ({:.2}x)",
+            threads,
+            elapsed,
+            baseline.as_secs_f64() / elapsed.as_secs_f64()
+        );
+    }
+    Ok(())
+}
+
+/// Best of several runs
+fn time_scan(root: &Path, threads: usize) -> io::Result<Duration> {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        Scanner::new(root).threads(threads).scan()?;
+        best = best.min(start.elapsed());
+    }
+    Ok(best)
+}
+
+/// Create (or reuse) a three-level tree with roughly `entries` folders and files
+fn fixture(entries: usize) -> io::Result<PathBuf> {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("planter-bench-{}", entries));
+    let marker = root.with_extension("complete");
+    if marker.exists() {
+        return Ok(root);
+    }
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+
+    let leaves = TOP_DIRS * SUB_DIRS * LEAF_DIRS;
+    let dirs = TOP_DIRS + TOP_DIRS * SUB_DIRS + leaves;
+    let files_per_leaf = entries.saturating_sub(dirs).div_ceil(leaves);
+
+    println!("generating fixture with ~{} entries...", dirs + files_per_leaf * leaves);
+    for top in 0..TOP_DIRS {
+        for sub in 0..SUB_DIRS {
+            for leaf in 0..LEAF_DIRS {
+                let dir = root
+                    .join(format!("top{:02}", top))
+                    .join(format!("sub{:02}", sub))
+                    .join(format!("leaf{:02}", leaf));
+                fs::create_dir_all(&dir)?;
+                for file in 0..files_per_leaf {
+                    fs::write(dir.join(format!("file{:04}.txt", file)), b"planter")?;
+                }
+            }
+        }
+    }
+    fs::write(marker, b"")?;
+    Ok(root)
+}
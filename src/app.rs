@@ -0,0 +1,1522 @@
+use crate::content::{Body, FileContent};
+use crate::finder::Finder;
+use crate::highlight::Highlighter;
+use crate::search::Search;
+use crate::sort::{Sort, SortKey};
+use crate::sunburst::{Dots, Sunburst};
+use crate::theme::Theme;
+use crate::timeline::Timeline;
+use crate::treemap::{self, ColorBy};
+use planter::{FileEntry, FileNode, LinkTarget, ScanEvent, Tree};
+use ratatui::layout::Rect;
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+pub const BREADCRUMB_SEPARATOR: &str = " › "; // Between the folders in a chart title
+
+/// What the left panel shows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum View {
+    #[default]
+    Tree,
+    Treemap,
+    Sunburst,
+}
+
+impl View {
+    pub fn next(self) -> Self {
+        match self {
+            View::Tree => View::Treemap,
+            View::Treemap => View::Sunburst,
+            View::Sunburst => View::Tree,
+        }
+    }
+
+    /// Start of the panel title; charts follow it with a breadcrumb
+    pub fn title(self) -> &'static str {
+        match self {
+            View::Tree => " Tree ",
+            View::Treemap => " Treemap: ",
+            View::Sunburst => " Sunburst: ",
+        }
+    }
+}
+
+pub struct App {
+    pub tree: Tree, // What is displayed
+    pub show_hidden: bool,
+    pub show_files: bool, // List files in the tree, not only folders
+    pub scanning: bool, // The background scan is still delivering nodes
+    pub animation_depth: usize, // Current depth level being animated
+    pub animation_complete: bool,
+    pub animate: bool, // Grow the tree level by level; otherwise show each level once scanned
+    pub apparent_size: bool, // Show file lengths instead of the space allocated on disk
+    pub sort: Sort, // Applied to the tree once the scan is finished
+    pub scroll_offset: usize,
+    pub selected_index: Option<usize>,
+    pub animation_frame: usize, // For root growth animation
+    pub preview_contents: Vec<PreviewItem>,
+    pub preview_error: Option<io::ErrorKind>, // Why the selected folder could not be listed
+    pub preview_file: Option<FileContent>, // The start of the selected file
+    highlighter: Highlighter, // Colors `preview_file` on a worker thread
+    pub preview_scroll_offset: usize,
+    pub preview_focused: bool, // Keys move the preview cursor instead of the tree selection
+    pub preview_selected: usize, // Cursor row in `preview_contents`
+    pub last_click_time: Option<Instant>,
+    pub last_click_index: Option<usize>,
+    pub last_preview_click: Option<(Instant, usize)>, // For double clicks in the preview
+    pub visible_node_indices_cache: Vec<usize>, // Cache for visible node indices
+    pub cache_valid: bool, // Track if cache needs refresh
+    pub collapsed: HashSet<PathBuf>, // Folders whose subtree is folded away
+    pub search: Search,
+    pub finder: Option<Finder>, // The Ctrl-P overlay, while open
+    pub errors_view: Option<usize>, // Highlighted row of the scan errors overlay, while open
+    pub timeline: Timeline,
+    pub timeline_focused: bool, // Keys pick buckets of the timeline in the Info panel
+    pub view: View,
+    pub chart_root: Option<PathBuf>, // Folder at the center of the treemap and sunburst; `None` for all roots
+    chart_root_at: Option<usize>,    // Index where `chart_root` was last found
+    chart_key: Option<ChartKey>,     // What the chart was last laid out for
+    pub chart_crumbs: Vec<(u16, u16, Option<PathBuf>)>, // Columns of each breadcrumb folder
+    pub treemap_color: ColorBy,
+    pub treemap_tiles: Vec<(usize, Rect)>, // Node and rectangle of each tile, as last drawn
+    pub sunburst: Sunburst, // Arcs as last drawn
+    pub sunburst_area: Rect, // Where the arcs were drawn
+    pub sunburst_dots: Dots, // The arcs drawn in `sunburst_area`
+    pub sunburst_hover: Option<usize>, // Node under the mouse pointer
+    pub theme: Theme,
+}
+
+/// What the layout of a chart depends on; it is only laid out again when one of these changes
+#[derive(Clone, Copy, PartialEq)]
+struct ChartKey {
+    view: View,
+    area: Rect,
+    root: Option<usize>,
+    nodes: usize,
+    scanning: bool, // The tree is sorted when the scan finishes
+    sort: Sort,
+    apparent_size: bool,
+    show_hidden: bool,
+    show_files: bool,
+    animation_depth: usize,
+}
+
+/// A child of the previewed folder as the scan has it
+enum Scanned<'a> {
+    Node(usize, &'a FileNode),
+    Entry(usize, &'a FileEntry),
+}
+
+#[derive(Clone)]
+pub struct PreviewItem {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64, // As shown in the tree, where the scan has the entry
+    pub file_count: usize,
+    pub modified: Option<SystemTime>,
+    pub link: Option<LinkTarget>,
+}
+
+impl App {
+    pub fn new(roots: &[PathBuf], show_hidden: bool, show_files: bool) -> Self {
+        App {
+            // Files without a node stay as entries, so they can be toggled at runtime
+            tree: Tree::new(roots)
+                .skip_hidden(!show_hidden)
+                .keep_files(show_files)
+                .keep_file_entries(true),
+            show_hidden,
+            show_files,
+            scanning: true,
+            animation_depth: 0,
+            animation_complete: false,
+            animate: true,
+            apparent_size: false,
+            sort: Sort::default(),
+            scroll_offset: 0,
+            selected_index: None,
+            animation_frame: 0,
+            preview_contents: Vec::new(),
+            preview_error: None,
+            preview_file: None,
+            highlighter: Highlighter::spawn(),
+            preview_scroll_offset: 0,
+            preview_focused: false,
+            preview_selected: 0,
+            last_click_time: None,
+            last_click_index: None,
+            last_preview_click: None,
+            visible_node_indices_cache: Vec::new(),
+            cache_valid: false,
+            collapsed: HashSet::new(),
+            search: Search::default(),
+            finder: None,
+            errors_view: None,
+            timeline: Timeline::default(),
+            timeline_focused: false,
+            view: View::Tree,
+            chart_root: None,
+            chart_root_at: None,
+            chart_key: None,
+            chart_crumbs: Vec::new(),
+            treemap_color: ColorBy::Kind,
+            treemap_tiles: Vec::new(),
+            sunburst: Sunburst::default(),
+            sunburst_area: Rect::default(),
+            sunburst_dots: Dots::default(),
+            sunburst_hover: None,
+            theme: Theme::default(),
+        }
+    }
+
+    /// Fold a message from the background scan into the tree
+    pub fn apply_scan_event(&mut self, event: ScanEvent) {
+        let finished = matches!(event, ScanEvent::Finished);
+        self.tree.apply(event);
+        self.cache_valid = false;
+        if finished {
+            self.scanning = false;
+            self.apply_sort();
+        }
+        if self.search.is_active() {
+            self.search.refresh(&self.tree.nodes, self.show_files);
+        }
+        if let Some(finder) = &mut self.finder {
+            finder.update(&self.tree.nodes, &self.tree.roots);
+        }
+        self.timeline.refresh(&self.tree);
+
+        // Select the first folder by default as soon as it arrives
+        if self.selected_index.is_none() && !self.tree.nodes.is_empty() {
+            self.selected_index = Some(0);
+            self.update_preview(0);
+        }
+    }
+
+    pub fn increment_animation(&mut self) {
+        let all_levels = self.tree.stats.max_depth + 1;
+        if !self.animate && self.animation_depth < all_levels {
+            self.animation_depth = all_levels;
+            self.cache_valid = false;
+        } else if self.animation_depth <= self.tree.stats.max_depth {
+            self.animation_depth += 1;
+            self.cache_valid = false; // Invalidate cache when animation progresses
+        } else if !self.scanning {
+            self.animation_complete = true;
+        }
+        // Increment frame for smooth animation within current rendering
+        if !self.animation_complete {
+            self.animation_frame = (self.animation_frame + 1) % 3;
+        }
+    }
+
+    pub fn is_node_visible(&self, node: &FileNode) -> bool {
+        node.depth <= self.animation_depth && (node.is_dir || self.show_files)
+    }
+
+    /// Whether a node has children in the tree as currently shown
+    pub fn has_children(&self, node: &FileNode) -> bool {
+        node.dir_count > 0 || (self.show_files && node.file_count > 0)
+    }
+
+    pub fn is_double_click(&self, idx: usize, now: Instant) -> bool {
+        if let (Some(last_time), Some(last_idx)) = (self.last_click_time, self.last_click_index) {
+            last_idx == idx && now.duration_since(last_time) < Duration::from_millis(500)
+        } else {
+            false
+        }
+    }
+
+    pub fn handle_mouse_click(&mut self, row: u16, area: Rect) {
+        self.preview_focused = false;
+        self.timeline_focused = false;
+        if !self.animation_complete {
+            return;
+        }
+
+        // Calculate which item was clicked (accounting for borders and scroll)
+        if row > area.top() && row < area.bottom() - 1 {
+            let clicked_index = (row - area.top() - 1) as usize + self.scroll_offset;
+            let Some(&idx) = self.get_visible_node_indices().get(clicked_index) else {
+                return;
+            };
+
+            let now = Instant::now();
+            if self.is_double_click(idx, now) {
+                // Second click on same item - open it
+                let _ = opener::open(&self.tree.nodes[idx].path);
+                // Reset click tracking after opening
+                self.last_click_time = None;
+                self.last_click_index = None;
+            } else {
+                // First click - select it
+                self.selected_index = Some(idx);
+                self.update_preview(idx);
+                self.last_click_time = Some(now);
+                self.last_click_index = Some(idx);
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn scroll_up(&mut self) {
+        if self.scroll_offset > 0 {
+            self.scroll_offset -= 1;
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn scroll_down(&mut self, visible_lines: usize) {
+        let visible_count = self.get_visible_node_indices().len();
+        let max_scroll = visible_count.saturating_sub(visible_lines);
+        if self.scroll_offset < max_scroll {
+            self.scroll_offset += 1;
+        }
+    }
+
+    pub fn get_visible_node_indices(&mut self) -> &Vec<usize> {
+        if !self.cache_valid {
+            let mut visible = Vec::new();
+            let mut folded_depth = None; // Depth of the collapsed folder we are inside, if any
+            for (idx, node) in self.tree.nodes.iter().enumerate() {
+                if let Some(depth) = folded_depth {
+                    if node.depth > depth {
+                        continue;
+                    }
+                    folded_depth = None;
+                }
+                if !self.is_node_visible(node) {
+                    continue;
+                }
+                if self.search.is_filtering() && !self.search.keep.get(idx).copied().unwrap_or(false) {
+                    continue;
+                }
+                if !self.timeline.keeps(idx) {
+                    continue;
+                }
+                visible.push(idx);
+                if !self.collapsed.is_empty() && self.collapsed.contains(&node.path) {
+                    folded_depth = Some(node.depth);
+                }
+            }
+            self.visible_node_indices_cache = visible;
+            self.cache_valid = true;
+
+            // Keep the scroll position within the (possibly shorter) list
+            let max_scroll = self.visible_node_indices_cache.len().saturating_sub(1);
+            self.scroll_offset = self.scroll_offset.min(max_scroll);
+        }
+        &self.visible_node_indices_cache
+    }
+
+    pub fn is_collapsed(&self, idx: usize) -> bool {
+        self.tree
+            .nodes
+            .get(idx)
+            .is_some_and(|node| self.collapsed.contains(&node.path))
+    }
+
+    fn set_collapsed(&mut self, idx: usize, collapse: bool) {
+        let Some(node) = self.tree.nodes.get(idx) else {
+            return;
+        };
+        if collapse && self.has_children(node) {
+            self.collapsed.insert(node.path.clone());
+        } else if !collapse {
+            self.collapsed.remove(&node.path);
+        }
+        self.cache_valid = false;
+    }
+
+    /// Fold the selected folder, or move to its parent if it is already folded
+    pub fn collapse_selected(&mut self) {
+        if !self.animation_complete {
+            return;
+        }
+        let Some(idx) = self.selected_index else {
+            return;
+        };
+        let node = &self.tree.nodes[idx];
+        if self.has_children(node) && !self.is_collapsed(idx) {
+            self.set_collapsed(idx, true);
+        } else if let Some(parent) = node.parent {
+            self.select(parent);
+        }
+    }
+
+    /// Unfold the selected folder, or move to its first child if it is already open
+    pub fn expand_selected(&mut self) {
+        if !self.animation_complete {
+            return;
+        }
+        let Some(idx) = self.selected_index else {
+            return;
+        };
+        if self.is_collapsed(idx) {
+            self.set_collapsed(idx, false);
+        } else {
+            // In depth-first order the children come right after their parent
+            let depth = self.tree.nodes[idx].depth;
+            let child = self.tree.nodes[idx + 1..]
+                .iter()
+                .take_while(|node| node.depth > depth)
+                .position(|node| node.is_dir || self.show_files);
+            if let Some(offset) = child {
+                self.select(idx + 1 + offset);
+            }
+        }
+    }
+
+    pub fn toggle_selected(&mut self) {
+        if !self.animation_complete {
+            return;
+        }
+        if let Some(idx) = self.selected_index {
+            let collapse = !self.is_collapsed(idx);
+            self.set_collapsed(idx, collapse);
+        }
+    }
+
+    /// Unfold the selected folder and everything below it
+    pub fn expand_all_below(&mut self) {
+        if !self.animation_complete {
+            return;
+        }
+        if let Some(node) = self.selected_index.and_then(|idx| self.tree.nodes.get(idx)) {
+            let root = node.path.clone();
+            self.collapsed.retain(|path| !path.starts_with(&root));
+            self.cache_valid = false;
+        }
+    }
+
+    /// Show folders down to `depth` and fold everything deeper
+    pub fn collapse_to_depth(&mut self, depth: usize) {
+        if !self.animation_complete {
+            return;
+        }
+        self.collapsed = self
+            .tree
+            .nodes
+            .iter()
+            .filter(|node| node.depth >= depth && self.has_children(node))
+            .map(|node| node.path.clone())
+            .collect();
+        self.cache_valid = false;
+
+        // Move the selection up to the folder that now hides it
+        if let Some(mut idx) = self.selected_index {
+            while self.tree.nodes[idx].depth > depth {
+                match self.tree.nodes[idx].parent {
+                    Some(parent) => idx = parent,
+                    None => break,
+                }
+            }
+            self.select(idx);
+        }
+    }
+
+    /// Open the search prompt; `filter` hides folders without matches
+    pub fn start_search(&mut self, filter: bool) {
+        self.search.editing = true;
+        self.search.filter = filter;
+        self.search.origin = self.selected_index;
+        self.cache_valid = false;
+    }
+
+    pub fn search_push(&mut self, c: char) {
+        self.search.query.push(c);
+        self.update_search();
+    }
+
+    pub fn search_pop(&mut self) {
+        self.search.query.pop();
+        self.update_search();
+    }
+
+    pub fn cycle_search_mode(&mut self) {
+        self.search.mode = self.search.mode.next();
+        self.update_search();
+    }
+
+    /// Close the prompt, keeping the results highlighted
+    pub fn finish_search(&mut self) {
+        self.search.editing = false;
+        if self.search.query.is_empty() {
+            self.clear_search();
+        }
+    }
+
+    /// Close the prompt and go back to where the search started
+    pub fn cancel_search(&mut self) {
+        let origin = self.search.origin;
+        self.clear_search();
+        if let Some(idx) = origin {
+            self.select(idx);
+        }
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search = Search::default();
+        self.cache_valid = false;
+    }
+
+    pub fn toggle_filter(&mut self) {
+        self.search.filter = !self.search.filter;
+        self.cache_valid = false;
+        self.select_match_if_hidden();
+    }
+
+    /// Jump to the next (or previous) match, wrapping around
+    pub fn next_match(&mut self, forward: bool) {
+        let matches = &self.search.matches;
+        if matches.is_empty() {
+            return;
+        }
+        let current = self.selected_index.unwrap_or(0);
+        let target = if forward {
+            matches.iter().find(|&&idx| idx > current).or(matches.first())
+        } else {
+            matches.iter().rev().find(|&&idx| idx < current).or(matches.last())
+        };
+        if let Some(&idx) = target {
+            self.reveal(idx);
+        }
+    }
+
+    // Recompile the query and move to the first match from where the search started
+    fn update_search(&mut self) {
+        self.search.compile();
+        self.search.refresh(&self.tree.nodes, self.show_files);
+        self.cache_valid = false;
+
+        let origin = self.search.origin.unwrap_or(0);
+        let first = self
+            .search
+            .matches
+            .iter()
+            .find(|&&idx| idx >= origin)
+            .or(self.search.matches.first())
+            .copied();
+        match first {
+            Some(idx) => self.reveal(idx),
+            None => self.select_match_if_hidden(),
+        }
+    }
+
+    // The filter may have hidden the selection; fall back to the first match or the root
+    fn select_match_if_hidden(&mut self) {
+        let Some(idx) = self.selected_index else {
+            return;
+        };
+        if self.search.is_filtering() && !self.search.keep.get(idx).copied().unwrap_or(false) {
+            let target = self.search.matches.first().copied().unwrap_or(0);
+            self.reveal(target);
+        }
+    }
+
+    pub fn open_finder(&mut self) {
+        self.finder = Some(self.new_finder());
+    }
+
+    fn new_finder(&self) -> Finder {
+        Finder::new(&self.tree.nodes, &self.tree.roots, self.show_files)
+    }
+
+    pub fn finder_push(&mut self, c: char) {
+        if let Some(finder) = &mut self.finder {
+            finder.query.push(c);
+            finder.selected = 0;
+            finder.update(&self.tree.nodes, &self.tree.roots);
+        }
+    }
+
+    pub fn finder_pop(&mut self) {
+        if let Some(finder) = &mut self.finder {
+            finder.query.pop();
+            finder.selected = 0;
+            finder.update(&self.tree.nodes, &self.tree.roots);
+        }
+    }
+
+    /// Close the finder and jump to the highlighted folder
+    pub fn accept_finder(&mut self) {
+        let Some(idx) = self.finder.take().and_then(|finder| finder.selected_node()) else {
+            return;
+        };
+        self.jump_to(idx);
+    }
+
+    /// Open the overlay listing the paths that could not be read
+    pub fn open_errors(&mut self) {
+        if !self.tree.errors.is_empty() {
+            self.errors_view = Some(0);
+        }
+    }
+
+    pub fn move_error_selection(&mut self, delta: isize) {
+        if let Some(selected) = &mut self.errors_view {
+            let last = self.tree.errors.len().saturating_sub(1);
+            *selected = selected.saturating_add_signed(delta).min(last);
+        }
+    }
+
+    /// Close the errors overlay and jump to the folder the highlighted error belongs to
+    pub fn accept_error(&mut self) {
+        let Some(error) = self.errors_view.take().and_then(|i| self.tree.errors.get(i)) else {
+            return;
+        };
+        // The deepest folder containing the failing path
+        let folder = self
+            .tree
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_dir && error.path.starts_with(&node.path))
+            .max_by_key(|(_, node)| node.depth)
+            .map(|(idx, _)| idx);
+        if let Some(idx) = folder {
+            self.jump_to(idx);
+        }
+    }
+
+    /// Select a node from an overlay, making sure neither folding nor the search filter hides it
+    fn jump_to(&mut self, idx: usize) {
+        // A folder hidden by the search filter could not be shown, so drop the filter
+        if self.search.is_filtering() && !self.search.keep.get(idx).copied().unwrap_or(false) {
+            self.search.filter = false;
+            self.cache_valid = false;
+        }
+        // Likewise for the timeline filter
+        if !self.timeline.keeps(idx) {
+            self.timeline.clear_filter();
+            self.cache_valid = false;
+        }
+        self.reveal(idx);
+    }
+
+    /// Select a node, unfolding any collapsed ancestors so it can be seen
+    pub fn reveal(&mut self, idx: usize) {
+        let mut current = self.tree.nodes.get(idx).and_then(|node| node.parent);
+        while let Some(parent) = current {
+            let node = &self.tree.nodes[parent];
+            if self.collapsed.remove(&node.path) {
+                self.cache_valid = false;
+            }
+            current = node.parent;
+        }
+        self.select(idx);
+    }
+
+    fn select(&mut self, idx: usize) {
+        if self.selected_index != Some(idx) {
+            self.selected_index = Some(idx);
+            self.update_preview(idx);
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.move_selection(-1);
+    }
+
+    pub fn select_next(&mut self) {
+        self.move_selection(1);
+    }
+
+    /// Move the selection by `delta` visible rows, stopping at either end
+    pub fn move_selection(&mut self, delta: isize) {
+        // Build visible nodes cache if needed
+        let _ = self.get_visible_node_indices();
+        let visible_nodes = &self.visible_node_indices_cache;
+
+        if visible_nodes.is_empty() {
+            return;
+        }
+
+        if let Some(current) = self.selected_index {
+            // Find current position in visible nodes
+            if let Some(pos) = visible_nodes.iter().position(|&idx| idx == current) {
+                let new_pos = pos.saturating_add_signed(delta).min(visible_nodes.len() - 1);
+                let new_idx = visible_nodes[new_pos];
+                self.select(new_idx);
+            }
+        }
+    }
+
+    pub fn ensure_selected_visible(&mut self, visible_lines: usize) {
+        if let Some(selected_idx) = self.selected_index {
+            // Build visible nodes cache if needed
+            let _ = self.get_visible_node_indices();
+            let visible_nodes = &self.visible_node_indices_cache;
+
+            if let Some(pos) = visible_nodes.iter().position(|&idx| idx == selected_idx) {
+                // Scroll up if selected is above visible area
+                if pos < self.scroll_offset {
+                    self.scroll_offset = pos;
+                }
+                // Scroll down if selected is below visible area
+                else if pos >= self.scroll_offset + visible_lines {
+                    self.scroll_offset = pos.saturating_sub(visible_lines - 1);
+                }
+            }
+        }
+    }
+
+    /// Open the selected folder in the default file manager, or the selected
+    /// file in its default application
+    pub fn open_selected(&self) {
+        if !self.animation_complete {
+            return;
+        }
+        if let Some(node) = self.selected_index.and_then(|idx| self.tree.nodes.get(idx)) {
+            let _ = opener::open(&node.path);
+        }
+    }
+
+    /// Switch to the next sort key
+    pub fn cycle_sort(&mut self) {
+        self.sort.key = self.sort.key.next();
+        self.apply_sort();
+    }
+
+    pub fn reverse_sort(&mut self) {
+        self.sort.descending = !self.sort.descending;
+        self.apply_sort();
+    }
+
+    /// Switch between apparent sizes and disk usage, re-sorting if sizes decide the order
+    pub fn toggle_apparent_size(&mut self) {
+        self.apparent_size = !self.apparent_size;
+        if self.sort.key == SortKey::Size {
+            self.apply_sort();
+        } else if let Some(idx) = self.selected_index {
+            self.update_preview(idx);
+        }
+    }
+
+    /// Reorder the siblings in the tree and the preview, staying on the selected path.
+    ///
+    /// A running scan still appends to the tree, so it is only sorted once
+    /// it has finished; the preview is sorted right away.
+    pub fn apply_sort(&mut self) {
+        if !self.scanning {
+            let selected = self
+                .selected_index
+                .map(|idx| self.tree.nodes[idx].path.clone());
+            self.sort_tree();
+            self.cache_valid = false;
+            self.last_click_index = None;
+            self.search.origin = None;
+
+            let nodes = &self.tree.nodes;
+            self.selected_index =
+                selected.and_then(|path| nodes.iter().position(|node| node.path == path));
+            if self.search.is_active() {
+                self.search.refresh(&self.tree.nodes, self.show_files);
+            }
+            if self.finder.is_some() {
+                self.finder = Some(self.new_finder());
+            }
+            self.timeline.reset(&self.tree);
+        }
+        if let Some(idx) = self.selected_index {
+            self.update_preview(idx);
+        }
+    }
+
+    fn sort_tree(&mut self) {
+        let (sort, apparent) = (self.sort, self.apparent_size);
+        self.tree.sort_by(|a, b| sort.compare_nodes(a, b, apparent));
+    }
+
+    /// Show or hide files in the tree; a selected file hands the selection to its folder
+    pub fn toggle_files(&mut self) {
+        self.show_files = !self.show_files;
+        self.cache_valid = false;
+        if let Some(idx) = self.selected_index {
+            let node = &self.tree.nodes[idx];
+            if !node.is_dir {
+                if let Some(parent) = node.parent {
+                    self.select(parent);
+                }
+            }
+        }
+        self.sync_file_nodes();
+        if self.search.is_active() {
+            self.search.refresh(&self.tree.nodes, self.show_files);
+            self.select_match_if_hidden();
+        }
+        if self.finder.is_some() {
+            self.finder = Some(self.new_finder());
+        }
+    }
+
+    /// Show or hide entries whose names start with a dot, without rescanning.
+    ///
+    /// The tree keeps hidden entries aside while they are not shown, so this
+    /// rebuilds its totals with or without them and carries the selection over
+    /// by path.
+    pub fn toggle_hidden(&mut self) {
+        self.show_hidden = !self.show_hidden;
+        let skip = !self.show_hidden;
+        self.rebuild_tree(|tree| tree.set_skip_hidden(skip));
+    }
+
+    /// Files get nodes only while the tree lists them or the treemap tiles them
+    fn needs_file_nodes(&self) -> bool {
+        self.show_files || self.view == View::Treemap
+    }
+
+    /// Turn files into nodes, or back into compact entries, to match [`App::needs_file_nodes`]
+    fn sync_file_nodes(&mut self) {
+        let keep = self.needs_file_nodes();
+        if keep != self.tree.keeps_files() {
+            self.rebuild_tree(|tree| tree.set_keep_files(keep));
+        }
+    }
+
+    /// Rebuild the tree with other settings, carrying the selection over by path
+    fn rebuild_tree(&mut self, change: impl FnOnce(&mut Tree)) {
+        let selected = self
+            .selected_index
+            .map(|idx| self.tree.nodes[idx].path.clone());
+        change(&mut self.tree);
+        if !self.scanning {
+            self.sort_tree();
+        }
+        self.cache_valid = false;
+        self.last_click_index = None;
+        self.search.origin = None;
+
+        // Stay on the same folder, or on its closest ancestor that is still shown
+        let nodes = &self.tree.nodes;
+        self.selected_index = selected
+            .and_then(|path| {
+                path.ancestors()
+                    .find_map(|dir| nodes.iter().position(|node| node.path == dir))
+            })
+            .or((!nodes.is_empty()).then_some(0));
+        if self.search.is_active() {
+            self.search.refresh(&self.tree.nodes, self.show_files);
+        }
+        if self.finder.is_some() {
+            self.finder = Some(self.new_finder());
+        }
+        self.timeline.reset(&self.tree);
+        self.select_kept_if_hidden();
+        if let Some(idx) = self.selected_index {
+            self.update_preview(idx);
+        }
+    }
+
+    pub fn update_preview(&mut self, node_index: usize) {
+        // Clear preview first
+        self.preview_contents.clear();
+        self.preview_scroll_offset = 0;
+        self.preview_selected = 0;
+        self.preview_error = None;
+        self.preview_file = None;
+
+        if node_index >= self.tree.nodes.len() {
+            return;
+        }
+
+        let node = &self.tree.nodes[node_index];
+        if !node.is_dir {
+            let path = node.path.clone();
+            self.show_file(&path);
+            return;
+        }
+        let node_path = &node.path;
+        // Sizes and counts come from the scan; the children directly follow their folder
+        let mut scanned: HashMap<&OsStr, Scanned> = self.tree.nodes[node_index + 1..]
+            .iter()
+            .zip(node_index + 1..)
+            .take_while(|(child, _)| child.depth > node.depth)
+            .filter(|(child, _)| child.parent == Some(node_index))
+            .filter_map(|(child, idx)| Some((child.path.file_name()?, Scanned::Node(idx, child))))
+            .collect();
+        let entries = self.tree.files_in(node_index);
+        scanned.extend(
+            entries.map(|(idx, entry)| (OsStr::new(&*entry.name), Scanned::Entry(idx, entry))),
+        );
+
+        match fs::read_dir(node_path) {
+            Ok(entries) => {
+                let mut items: Vec<PreviewItem> = entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| {
+                        self.show_hidden || !entry.file_name().to_string_lossy().starts_with('.')
+                    })
+                    // The timeline filter only knows the scanned entries
+                    .filter(|entry| {
+                        !self.timeline.is_filtering()
+                            || scanned
+                                .get(entry.file_name().as_os_str())
+                                .is_some_and(|scanned| match *scanned {
+                                    Scanned::Node(idx, _) => self.timeline.keeps(idx),
+                                    Scanned::Entry(idx, _) => self.timeline.keeps_entry(idx),
+                                })
+                    })
+                    .map(|entry| {
+                        let path = entry.path();
+                        let is_dir = path.is_dir();
+                        let link = entry
+                            .file_type()
+                            .is_ok_and(|t| t.is_symlink())
+                            .then(|| LinkTarget::read(&path))
+                            .flatten();
+                        // Like the scan without --follow-links, a link's size is its own
+                        let metadata = fs::symlink_metadata(&path).ok();
+                        let scanned = scanned.get(entry.file_name().as_os_str());
+                        let size = match (scanned, &metadata) {
+                            (Some(Scanned::Node(_, node)), _) => node.usage(self.apparent_size),
+                            (Some(Scanned::Entry(_, entry)), _) => entry.usage(self.apparent_size),
+                            (None, Some(metadata)) if !is_dir => metadata.len(),
+                            _ => 0,
+                        };
+                        let file_count = match scanned {
+                            Some(Scanned::Node(_, node)) => node.file_count,
+                            _ => 0, // Files count nothing below them
+                        };
+                        PreviewItem {
+                            name: entry.file_name().to_string_lossy().to_string(),
+                            is_dir,
+                            size,
+                            file_count,
+                            modified: metadata.and_then(|m| m.modified().ok()),
+                            link,
+                        }
+                    })
+                    .collect();
+
+                // Directories first, then files, each in the tree's sort order
+                let sort = self.sort;
+                items.sort_by(|a, b| sort.compare_items(a, b));
+
+                self.preview_contents = items;
+            }
+            Err(err) => self.preview_error = Some(err.kind()),
+        }
+    }
+
+    /// Preview the start of a file, colored once the highlighter is done with it
+    fn show_file(&mut self, path: &Path) {
+        let file = FileContent::load(path);
+        if let Body::Text { lines, .. } = &file.body {
+            self.highlighter.request(&file.path, lines);
+        }
+        self.preview_file = Some(file);
+    }
+
+    /// Color the previewed file if the highlighter has finished it
+    pub fn poll_highlighter(&mut self) {
+        let Some((path, colored)) = self.highlighter.try_recv() else {
+            return;
+        };
+        if let Some(file) = self.preview_file.as_mut().filter(|file| file.path == path) {
+            if let Body::Text { syntax, .. } = &mut file.body {
+                *syntax = colored;
+            }
+        }
+    }
+
+    pub fn toggle_preview_focus(&mut self) {
+        self.preview_focused = !self.preview_focused;
+        self.timeline_focused = false;
+    }
+
+    pub fn toggle_timeline_focus(&mut self) {
+        self.timeline_focused = !self.timeline_focused;
+        self.preview_focused = false;
+    }
+
+    /// Filter the tree and preview to the selected buckets of the timeline, or show everything again
+    pub fn toggle_time_filter(&mut self) {
+        self.timeline.toggle_filter(&self.tree);
+        self.after_time_filter();
+    }
+
+    pub fn zoom_timeline(&mut self, zoom_in: bool) {
+        if zoom_in {
+            self.timeline.zoom_in();
+        } else {
+            self.timeline.zoom_out();
+        }
+    }
+
+    pub fn cycle_timeline_granularity(&mut self) {
+        self.timeline.cycle_granularity();
+    }
+
+    /// Count another timestamp in the timeline; an active filter applies to it too
+    pub fn cycle_timeline_field(&mut self) {
+        self.timeline.cycle_field(&self.tree);
+        if self.timeline.is_filtering() {
+            self.after_time_filter();
+        }
+    }
+
+    fn after_time_filter(&mut self) {
+        self.cache_valid = false;
+        self.select_kept_if_hidden();
+        if let Some(idx) = self.selected_index {
+            self.update_preview(idx);
+        }
+    }
+
+    // The timeline filter may have hidden the selection; fall back to the closest folder it keeps
+    fn select_kept_if_hidden(&mut self) {
+        let mut current = self.selected_index;
+        while let Some(idx) = current {
+            if self.timeline.keeps(idx) {
+                break;
+            }
+            current = self.tree.nodes[idx].parent;
+        }
+        self.selected_index = current;
+    }
+
+    /// Move the preview cursor by `delta` rows, or scroll a file preview
+    pub fn move_preview_selection(&mut self, delta: isize, visible_lines: usize) {
+        if self.preview_file.is_some() {
+            if delta < 0 {
+                let lines = delta.unsigned_abs();
+                self.preview_scroll_offset = self.preview_scroll_offset.saturating_sub(lines);
+            } else {
+                self.scroll_preview_down(delta as usize);
+            }
+            return;
+        }
+        if self.preview_contents.is_empty() {
+            return;
+        }
+        let last = self.preview_contents.len() - 1;
+        self.preview_selected = self.preview_selected.saturating_add_signed(delta).min(last);
+        self.ensure_preview_selected_visible(visible_lines);
+    }
+
+    fn ensure_preview_selected_visible(&mut self, visible_lines: usize) {
+        if self.preview_selected < self.preview_scroll_offset {
+            self.preview_scroll_offset = self.preview_selected;
+        } else if self.preview_selected >= self.preview_scroll_offset + visible_lines {
+            self.preview_scroll_offset = self.preview_selected + 1 - visible_lines.max(1);
+        }
+    }
+
+    /// Path of the entry under the preview cursor, and whether it is a folder
+    fn preview_target(&self) -> Option<(PathBuf, bool)> {
+        let folder = self.tree.nodes.get(self.selected_index?)?;
+        let item = self.preview_contents.get(self.preview_selected)?;
+        Some((folder.path.join(&item.name), item.is_dir))
+    }
+
+    /// Enter on the preview: descend into the folder under the cursor, or open
+    /// the file under it (or the previewed file) in its default application
+    pub fn open_preview_selection(&mut self) {
+        if let Some(file) = &self.preview_file {
+            let _ = opener::open(&file.path);
+            return;
+        }
+        match self.preview_target() {
+            Some((path, true)) => self.descend_preview(&path),
+            Some((path, false)) => {
+                let _ = opener::open(path);
+            }
+            None => {}
+        }
+    }
+
+    /// Right on the preview: descend into the folder under the cursor, or show
+    /// the contents of the file under it
+    pub fn enter_preview_selection(&mut self) {
+        match self.preview_target() {
+            Some((path, true)) => self.descend_preview(&path),
+            Some((path, false)) if self.preview_file.is_none() => {
+                self.show_file(&path);
+                self.preview_scroll_offset = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Select a folder listed in the preview in the tree, which then lists its contents
+    fn descend_preview(&mut self, path: &Path) {
+        if !self.animation_complete {
+            return;
+        }
+        // Hidden or excluded folders aren't in the tree
+        if let Some(idx) = self.tree.nodes.iter().position(|node| node.path == path) {
+            self.reveal(idx);
+        }
+    }
+
+    /// Left on the preview: back from a listed file to its folder, or up to the
+    /// parent folder with the cursor on the one just left
+    pub fn leave_preview_selection(&mut self, visible_lines: usize) {
+        let Some(idx) = self.selected_index else {
+            return;
+        };
+        let node = &self.tree.nodes[idx];
+        if node.is_dir && self.preview_file.is_some() {
+            let shown = self.preview_selected;
+            self.update_preview(idx);
+            self.preview_selected = shown.min(self.preview_contents.len().saturating_sub(1));
+        } else if let Some(parent) = node.parent.filter(|_| self.animation_complete) {
+            let name = node.name.clone();
+            self.reveal(parent);
+            let items = &self.preview_contents;
+            if let Some(pos) = items.iter().position(|item| item.name == name) {
+                self.preview_selected = pos;
+            }
+        } else {
+            return;
+        }
+        self.ensure_preview_selected_visible(visible_lines);
+    }
+
+    /// Click in the preview: focus it and move the cursor; a double click enters the row
+    pub fn handle_preview_click(&mut self, row: u16, area: Rect) {
+        self.preview_focused = true;
+        if self.preview_file.is_some() || row <= area.top() || row >= area.bottom() - 1 {
+            return;
+        }
+        let pos = (row - area.top() - 1) as usize + self.preview_scroll_offset;
+        if pos >= self.preview_contents.len() {
+            return;
+        }
+        let now = Instant::now();
+        let double = self.last_preview_click.is_some_and(|(time, last)| {
+            last == pos && now.duration_since(time) < Duration::from_millis(500)
+        });
+        self.preview_selected = pos;
+        if double {
+            self.last_preview_click = None;
+            self.open_preview_selection();
+        } else {
+            self.last_preview_click = Some((now, pos));
+        }
+    }
+
+    pub fn scroll_preview_up(&mut self) {
+        if self.preview_scroll_offset > 0 {
+            self.preview_scroll_offset -= 1;
+        }
+    }
+
+    pub fn scroll_preview_down(&mut self, lines: usize) {
+        // Hex dumps are bounded by their narrowest layout; drawing clamps the rest
+        let rows = match &self.preview_file {
+            Some(file) => file.line_count(4),
+            None => self.preview_contents.len(),
+        };
+        if rows == 0 {
+            return;
+        }
+        let max_offset = rows.saturating_sub(1);
+        self.preview_scroll_offset = (self.preview_scroll_offset + lines).min(max_offset);
+    }
+
+    /// Switch the left panel between the tree, the treemap and the sunburst
+    pub fn cycle_view(&mut self) {
+        let from = self.view;
+        self.view = self.view.next();
+        self.sync_file_nodes();
+        let Some(idx) = self.selected_index else {
+            return;
+        };
+        match self.view {
+            View::Tree => {
+                // Files picked in a chart may not be listed in the tree
+                let node = &self.tree.nodes[idx];
+                let shown = node.is_dir || self.show_files;
+                let idx = if shown { Some(idx) } else { node.parent };
+                if let Some(idx) = idx {
+                    self.reveal(idx);
+                }
+            }
+            _ if from == View::Tree => {
+                // Chart the selected folder, or the folder of the selected file
+                let node = &self.tree.nodes[idx];
+                let folder = if node.is_dir { Some(idx) } else { node.parent };
+                self.chart_root = folder.map(|idx| self.tree.nodes[idx].path.clone());
+                if self.chart_root.is_none() && self.tree.roots.len() == 1 {
+                    self.chart_root = self.tree.roots.first().cloned();
+                }
+            }
+            _ => {} // Charts share the folder they show
+        }
+    }
+
+    pub fn cycle_treemap_color(&mut self) {
+        self.treemap_color = self.treemap_color.next();
+    }
+
+    pub fn chart_root_index(&self) -> Option<usize> {
+        let root = self.chart_root.as_ref()?;
+        let is_root = |node: &FileNode| node.path == *root;
+        match self.chart_root_at {
+            Some(idx) if self.tree.nodes.get(idx).is_some_and(is_root) => Some(idx),
+            _ => self.tree.nodes.iter().position(is_root),
+        }
+    }
+
+    /// Children of a folder (or the roots) that take up space, largest first
+    fn chart_children(&self, folder: Option<usize>) -> Vec<usize> {
+        let mut children: Vec<usize> = match folder {
+            Some(folder) => {
+                let depth = self.tree.nodes[folder].depth;
+                (folder + 1..self.tree.nodes.len())
+                    .take_while(|&idx| self.tree.nodes[idx].depth > depth)
+                    .filter(|&idx| self.tree.nodes[idx].parent == Some(folder))
+                    .collect()
+            }
+            None => (0..self.tree.nodes.len())
+                .filter(|&idx| self.tree.nodes[idx].parent.is_none())
+                .collect(),
+        };
+        let size = |idx: usize| self.tree.nodes[idx].usage(self.apparent_size);
+        children.retain(|&idx| size(idx) > 0);
+        children.sort_by_key(|&idx| std::cmp::Reverse(size(idx)));
+        children
+    }
+
+    /// Folders from the top down to the charted one, for the breadcrumb
+    pub fn chart_breadcrumb(&self) -> Vec<(String, Option<PathBuf>)> {
+        let mut crumbs = Vec::new();
+        let mut current = self.chart_root_index();
+        while let Some(idx) = current {
+            let node = &self.tree.nodes[idx];
+            crumbs.push((node.name.clone(), Some(node.path.clone())));
+            current = node.parent;
+        }
+        if self.tree.roots.len() > 1 || crumbs.is_empty() {
+            crumbs.push(("All".to_string(), None));
+        }
+        crumbs.reverse();
+        crumbs
+    }
+
+    /// Lay out the chart for the panel in `area`, unless nothing it depends on changed
+    pub fn layout_chart(&mut self, area: Rect) {
+        self.chart_root_at = self.chart_root_index();
+        if self.chart_root.is_some() && self.chart_root_at.is_none() {
+            // Gone from the tree, e.g. hidden by the dot-file toggle
+            self.chart_root = None;
+        }
+        let key = ChartKey {
+            view: self.view,
+            area,
+            root: self.chart_root_at,
+            nodes: self.tree.nodes.len(),
+            scanning: self.scanning,
+            sort: self.sort,
+            apparent_size: self.apparent_size,
+            show_hidden: self.show_hidden,
+            show_files: self.show_files,
+            animation_depth: self.animation_depth,
+        };
+        if self.chart_key == Some(key) {
+            return;
+        }
+        self.chart_key = Some(key);
+        self.layout_breadcrumb(area);
+        match self.view {
+            View::Tree => {}
+            View::Treemap => self.layout_treemap(area),
+            View::Sunburst => self.layout_sunburst(area),
+        }
+    }
+
+    /// Where the breadcrumb folders end up in the title of a chart panel at `area`
+    fn layout_breadcrumb(&mut self, area: Rect) {
+        let mut column = area.x + 1 + self.view.title().chars().count() as u16;
+        self.chart_crumbs.clear();
+        for (pos, (name, path)) in self.chart_breadcrumb().into_iter().enumerate() {
+            if pos > 0 {
+                column += BREADCRUMB_SEPARATOR.chars().count() as u16;
+            }
+            let end = column + ratatui::text::Span::raw(name).width() as u16;
+            self.chart_crumbs.push((column, end, path));
+            column = end;
+        }
+    }
+
+    /// Lay out the tiles for the treemap panel in `area`
+    fn layout_treemap(&mut self, area: Rect) {
+        let children = self.chart_children(self.chart_root_index());
+        let sizes: Vec<u64> = children
+            .iter()
+            .map(|&idx| self.tree.nodes[idx].usage(self.apparent_size))
+            .collect();
+        self.treemap_tiles = children
+            .into_iter()
+            .zip(treemap::squarify(&sizes, inner(area)))
+            .filter(|(_, rect)| !rect.is_empty())
+            .collect();
+    }
+
+    /// Lay out the arcs for the sunburst panel in `area`
+    fn layout_sunburst(&mut self, area: Rect) {
+        // Rings grow in level by level, like the tree
+        let shown = |node: &FileNode| self.is_node_visible(node);
+        let root = self.chart_root_index();
+        self.sunburst = Sunburst::new(&self.tree.nodes, root, self.apparent_size, shown);
+        self.sunburst_area = inner(area);
+        self.sunburst_dots = self.sunburst.dots(self.sunburst_area);
+    }
+
+    /// The node shown by the chart that is selected, or that holds the selection
+    pub fn chart_selected(&self) -> Option<usize> {
+        let mut current = self.selected_index;
+        while let Some(idx) = current {
+            let charted = match self.view {
+                View::Treemap => self.treemap_tiles.iter().any(|&(node, _)| node == idx),
+                View::Sunburst => self.sunburst.position(idx).is_some(),
+                View::Tree => true,
+            };
+            if charted {
+                return Some(idx);
+            }
+            current = self.tree.nodes[idx].parent;
+        }
+        None
+    }
+
+    /// Select the largest node next to the center of the chart
+    fn select_first_charted(&mut self) {
+        let first = match self.view {
+            View::Treemap => self.treemap_tiles.first().map(|&(node, _)| node),
+            _ => self.chart_children(self.chart_root_index()).first().copied(),
+        };
+        if let Some(node) = first {
+            self.select(node);
+        }
+    }
+
+    /// Move to the nearest tile in a direction, given as steps along x and y
+    pub fn move_treemap_selection(&mut self, dx: i32, dy: i32) {
+        let current = self
+            .chart_selected()
+            .and_then(|idx| self.treemap_tiles.iter().find(|&&(node, _)| node == idx));
+        let Some(&(_, from)) = current else {
+            self.select_first_charted();
+            return;
+        };
+        let center = |rect: Rect| {
+            (
+                rect.x as i32 * 2 + rect.width as i32,
+                rect.y as i32 * 2 + rect.height as i32,
+            )
+        };
+        let (cx, cy) = center(from);
+        let next = self
+            .treemap_tiles
+            .iter()
+            .filter(|(_, rect)| match (dx, dy) {
+                (1, _) => rect.x >= from.right(),
+                (-1, _) => rect.right() <= from.x,
+                (_, 1) => rect.y >= from.bottom(),
+                _ => rect.bottom() <= from.y,
+            })
+            .min_by_key(|(_, rect)| {
+                let (x, y) = center(*rect);
+                // Prefer tiles straight ahead over ones off to the side
+                let offset = (x - cx, y - cy);
+                let (along, across) = if dx != 0 {
+                    offset
+                } else {
+                    (offset.1, offset.0)
+                };
+                along.abs() + 2 * across.abs()
+            });
+        if let Some(&(node, _)) = next {
+            self.select(node);
+        }
+    }
+
+    /// Move to the next arc clockwise (or counterclockwise) on the same ring
+    pub fn move_sunburst_around(&mut self, delta: isize) {
+        let selected = self.chart_selected();
+        let Some(pos) = selected.and_then(|idx| self.sunburst.position(idx)) else {
+            self.select_first_charted();
+            return;
+        };
+        let ring = self.sunburst.ring(self.sunburst.segments[pos].ring);
+        let Some(at) = ring.iter().position(|&seg| seg == pos) else {
+            return;
+        };
+        let next = (at as isize + delta).rem_euclid(ring.len() as isize) as usize;
+        self.select(self.sunburst.segments[ring[next]].node);
+    }
+
+    /// Move out to the first arc clockwise on the ring below the selected one
+    pub fn move_sunburst_to_child(&mut self) {
+        let Some(idx) = self.chart_selected() else {
+            self.select_first_charted();
+            return;
+        };
+        // The center has no arc; its children are on the first ring
+        let below = self
+            .sunburst
+            .position(idx)
+            .map_or(1, |pos| self.sunburst.segments[pos].ring + 1);
+        let ring = self.sunburst.ring(below);
+        let child = ring
+            .iter()
+            .map(|&seg| self.sunburst.segments[seg].node)
+            .find(|&node| self.tree.nodes[node].parent == Some(idx));
+        if let Some(child) = child {
+            self.select(child);
+        }
+    }
+
+    /// Move in to the arc of the parent folder, stopping at the innermost ring
+    pub fn move_sunburst_to_parent(&mut self) {
+        let parent = self
+            .chart_selected()
+            .and_then(|idx| self.tree.nodes[idx].parent)
+            .filter(|&parent| self.sunburst.position(parent).is_some());
+        if let Some(parent) = parent {
+            self.select(parent);
+        }
+    }
+
+    /// Chart the selected folder's children, or open the selected file
+    pub fn drill_down(&mut self) {
+        let Some(idx) = self.chart_selected() else {
+            self.select_first_charted();
+            return;
+        };
+        let node = &self.tree.nodes[idx];
+        if !node.is_dir {
+            let _ = opener::open(&node.path);
+            return;
+        }
+        if let Some(&first) = self.chart_children(Some(idx)).first() {
+            self.chart_root = Some(node.path.clone());
+            self.select(first);
+        }
+    }
+
+    /// Chart the parent of the charted folder, with the folder just left selected
+    pub fn drill_up(&mut self) {
+        let Some(root) = self.chart_root_index() else {
+            return;
+        };
+        let parent = self.tree.nodes[root].parent;
+        self.chart_root = match parent {
+            Some(parent) => Some(self.tree.nodes[parent].path.clone()),
+            None if self.tree.roots.len() > 1 => None,
+            None => return, // A single root is as far up as it goes
+        };
+        self.select(root);
+    }
+
+    /// Go back to the breadcrumb folder at `column`, if there is one
+    fn click_breadcrumb(&mut self, column: u16) {
+        let crumb = self
+            .chart_crumbs
+            .iter()
+            .find(|(start, end, _)| (*start..*end).contains(&column));
+        let Some((_, _, path)) = crumb.filter(|(_, _, path)| *path != self.chart_root) else {
+            return;
+        };
+        let left = self.chart_root_index();
+        self.chart_root = path.clone();
+        if let Some(idx) = left {
+            // Select the way back down to where we were
+            let root = self.chart_root_index();
+            let mut current = idx;
+            while let Some(parent) = self.tree.nodes[current].parent {
+                if Some(parent) == root {
+                    break;
+                }
+                current = parent;
+            }
+            self.select(current);
+        }
+    }
+
+    /// Select a charted node, or drill into it on a double click
+    fn click_chart_node(&mut self, idx: usize) {
+        let now = Instant::now();
+        if self.is_double_click(idx, now) {
+            self.last_click_time = None;
+            self.last_click_index = None;
+            self.drill_down();
+        } else {
+            self.select(idx);
+            self.last_click_time = Some(now);
+            self.last_click_index = Some(idx);
+        }
+    }
+
+    /// Click in the treemap: select a tile, drill into it on a double click, or
+    /// go back to a folder of the breadcrumb
+    pub fn handle_treemap_click(&mut self, column: u16, row: u16, area: Rect) {
+        if row == area.top() {
+            self.click_breadcrumb(column);
+            return;
+        }
+        let position = ratatui::layout::Position::new(column, row);
+        let tile = self
+            .treemap_tiles
+            .iter()
+            .find(|(_, rect)| rect.contains(position));
+        if let Some(&(idx, _)) = tile {
+            self.click_chart_node(idx);
+        }
+    }
+
+    /// Click in the sunburst: like the treemap, with the center going back up
+    pub fn handle_sunburst_click(&mut self, column: u16, row: u16, area: Rect) {
+        if row == area.top() {
+            self.click_breadcrumb(column);
+            return;
+        }
+        match self.sunburst_at(column, row) {
+            Some((0, _)) => self.drill_up(),
+            Some((_, Some(idx))) => self.click_chart_node(idx),
+            _ => {}
+        }
+    }
+
+    /// Show details of the arc under the mouse pointer
+    pub fn hover_sunburst(&mut self, column: u16, row: u16) {
+        self.sunburst_hover = match self.sunburst_at(column, row) {
+            Some((0, _)) => self.chart_root_index(),
+            Some((_, node)) => node,
+            None => None,
+        };
+    }
+
+    /// Ring and node under a terminal cell of the sunburst, if it is on the chart
+    fn sunburst_at(&self, column: u16, row: u16) -> Option<(usize, Option<usize>)> {
+        let area = self.sunburst_area;
+        let position = ratatui::layout::Position::new(column, row);
+        if !area.contains(position) {
+            return None;
+        }
+        // The middle of the cell, in braille dots from the center with y up
+        let dx = (column - area.x) as f64 * 2.0 + 1.0 - area.width as f64;
+        let dy = area.height as f64 * 2.0 - ((row - area.y) as f64 * 4.0 + 2.0);
+        let (ring, segment) = self.sunburst.locate(dx, dy, area)?;
+        Some((ring, segment.map(|seg| self.sunburst.segments[seg].node)))
+    }
+}
+
+/// The inside of a bordered panel
+fn inner(area: Rect) -> Rect {
+    Rect::new(
+        area.x + 1,
+        area.y + 1,
+        area.width.saturating_sub(2),
+        area.height.saturating_sub(2),
+    )
+}
@@ -0,0 +1,209 @@
+use crate::sort::{Sort, SortKey};
+use crate::theme::ThemeName;
+use clap::{Args, ColorChoice, Parser, Subcommand, ValueEnum};
+use globset::Glob;
+use planter::Scanner;
+use std::path::PathBuf;
+
+/// Explore folder trees and disk usage in the terminal
+#[derive(Parser, Debug)]
+#[command(version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub scan: ScanArgs,
+
+    #[command(flatten)]
+    pub view: ViewArgs,
+
+    /// Print the tree as text instead of starting the interactive view
+    /// (the default when stdout is not a terminal, or with any of the
+    /// options below)
+    #[arg(long)]
+    pub print: bool,
+
+    #[command(flatten)]
+    pub output: PrintArgs,
+}
+
+/// Non-interactive outputs; without one the interactive tree is started
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Print the folder tree as text, like `--print`
+    Tree {
+        #[command(flatten)]
+        scan: ScanArgs,
+
+        #[command(flatten)]
+        output: PrintArgs,
+    },
+    /// Print the folder and file totals and the file timeline
+    Stats {
+        #[command(flatten)]
+        scan: ScanArgs,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+}
+
+/// Options that control what gets scanned
+#[derive(Args, Debug)]
+pub struct ScanArgs {
+    /// Folders to scan; each one becomes a separate root
+    #[arg(value_name = "PATH", default_value = ".", value_parser = existing_dir)]
+    pub paths: Vec<PathBuf>,
+
+    /// Do not descend more than N levels below each root
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Skip entries whose name or relative path matches GLOB (repeatable)
+    #[arg(long, value_name = "GLOB", value_parser = glob_pattern)]
+    pub exclude: Vec<String>,
+
+    /// Only count files whose name or relative path matches GLOB (repeatable)
+    #[arg(long, value_name = "GLOB", value_parser = glob_pattern)]
+    pub include: Vec<String>,
+
+    /// Include files and folders whose names start with a dot
+    #[arg(long)]
+    pub hidden: bool,
+
+    /// Do not skip entries listed in .gitignore, .ignore and the global git excludes file
+    #[arg(long)]
+    pub no_ignore: bool,
+
+    /// Follow symbolic links to folders
+    #[arg(long)]
+    pub follow_links: bool,
+
+    /// Do not descend into folders on other filesystems (mount points)
+    #[arg(short = 'x', long)]
+    pub one_file_system: bool,
+
+    /// Threads used to read folders (0 = one per CPU)
+    #[arg(short = 'j', long, value_name = "N", default_value_t = 0)]
+    pub threads: usize,
+}
+
+impl ScanArgs {
+    pub fn scanner(&self) -> Scanner {
+        let mut scanner = Scanner::new(&self.paths[0]);
+        for path in &self.paths[1..] {
+            scanner = scanner.add_root(path);
+        }
+        for pattern in &self.exclude {
+            scanner = scanner.exclude(pattern);
+        }
+        for pattern in &self.include {
+            scanner = scanner.include(pattern);
+        }
+        scanner
+            .max_depth(self.max_depth)
+            .skip_hidden(!self.hidden)
+            .ignore_files(!self.no_ignore)
+            .follow_links(self.follow_links)
+            .one_file_system(self.one_file_system)
+            .threads(self.threads)
+    }
+}
+
+/// Options for the interactive tree
+#[derive(Args, Debug)]
+pub struct ViewArgs {
+    /// Milliseconds between animation frames
+    #[arg(long, value_name = "MS", default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    pub speed: u64,
+
+    /// Show the whole tree right away instead of growing it level by level
+    #[arg(long)]
+    pub no_animation: bool,
+
+    /// List files in the tree as well as folders (toggle with f)
+    #[arg(long)]
+    pub files: bool,
+
+    /// Color theme, also used for colored text output
+    #[arg(long, value_enum, global = true, default_value_t = ThemeName::Default)]
+    pub theme: ThemeName,
+
+    /// Show apparent file sizes instead of the space used on disk, like `du --apparent-size`
+    #[arg(long, global = true)]
+    pub apparent_size: bool,
+
+    /// Order of the folders (and files) within each folder
+    #[arg(long, value_enum, global = true, default_value_t = SortKey::Name)]
+    pub sort: SortKey,
+
+    /// Sort in descending order
+    #[arg(short, long, global = true)]
+    pub reverse: bool,
+}
+
+impl ViewArgs {
+    pub fn sort(&self) -> Sort {
+        Sort {
+            key: self.sort,
+            descending: self.reverse,
+        }
+    }
+}
+
+/// How non-interactive output is written
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Human-readable text
+    Text,
+    /// One JSON document with the folders nested under their roots
+    Json,
+    /// One JSON object per line: every folder, then the statistics
+    Ndjson,
+}
+
+/// Options for the printed tree
+#[derive(Args, Debug)]
+pub struct PrintArgs {
+    /// Output format; JSON always includes sizes, counts and timestamps
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+
+    /// Show the cumulative size of each folder
+    #[arg(short, long)]
+    pub sizes: bool,
+
+    /// Show how many files and folders each folder contains
+    #[arg(short, long)]
+    pub counts: bool,
+
+    /// When to color the output
+    #[arg(long, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+}
+
+impl PrintArgs {
+    /// Whether any option only the printed tree uses was given, which implies `--print`
+    pub fn is_set(&self) -> bool {
+        self.format != Format::Text || self.sizes || self.counts || self.color != ColorChoice::Auto
+    }
+}
+
+fn existing_dir(arg: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(arg);
+    if !path.exists() {
+        return Err(format!("path '{}' does not exist", arg));
+    }
+    if !path.is_dir() {
+        return Err(format!("path '{}' is not a directory", arg));
+    }
+    Ok(path)
+}
+
+fn glob_pattern(arg: &str) -> Result<String, String> {
+    Glob::new(arg)
+        .map(|_| arg.to_string())
+        .map_err(|e| e.kind().to_string())
+}
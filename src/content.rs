@@ -0,0 +1,275 @@
+use crate::highlight::Highlighted;
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+const READ_LIMIT: u64 = 64 * 1024; // Bytes read from the start of a file
+const MAX_LINES: usize = 500; // Text lines kept for the preview
+const SNIFF_LEN: usize = 8 * 1024; // Bytes inspected to tell text from binary
+
+/// How the bytes of a previewed file were decoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1, // Fallback for text that is not valid UTF-8
+}
+
+impl Encoding {
+    pub fn label(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Latin1 => "Latin-1",
+        }
+    }
+}
+
+/// What the preview shows for a file
+pub enum Body {
+    Text {
+        lines: Vec<String>,
+        encoding: Encoding,
+        syntax: Option<Highlighted>, // Set once colored in the background, if the language is recognized
+    },
+    Binary(Vec<u8>), // Shown as a hex dump sized to the pane
+    Unreadable(io::ErrorKind),
+    Special, // FIFOs, sockets and devices are never opened
+}
+
+/// The start of a file, decoded for the preview pane
+pub struct FileContent {
+    pub path: PathBuf,
+    pub size: u64,
+    pub body: Body,
+    pub truncated: bool, // Only the first `READ_LIMIT` bytes or `MAX_LINES` lines are kept
+}
+
+impl FileContent {
+    pub fn load(path: &Path) -> Self {
+        let (body, size, truncated) = match read_start(path) {
+            Ok(Some((bytes, size))) => {
+                let (body, truncated) = decode(bytes);
+                (body, size, truncated || size > READ_LIMIT)
+            }
+            Ok(None) => (Body::Special, 0, false),
+            Err(err) => (Body::Unreadable(err.kind()), 0, false),
+        };
+        FileContent {
+            path: path.to_path_buf(),
+            size,
+            body,
+            truncated,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Rows the body takes up, for scrolling; hex rows depend on the pane width
+    pub fn line_count(&self, bytes_per_row: usize) -> usize {
+        match &self.body {
+            Body::Text { lines, .. } => lines.len(),
+            Body::Binary(bytes) => bytes.len().div_ceil(bytes_per_row.max(1)),
+            Body::Unreadable(_) | Body::Special => 1,
+        }
+    }
+}
+
+/// Turn the bytes read into text lines or a hex dump; also tells if lines were dropped
+fn decode(bytes: Vec<u8>) -> (Body, bool) {
+    let Some((text, encoding)) = decode_text(&bytes) else {
+        return (Body::Binary(bytes), false);
+    };
+    let mut lines: Vec<String> = text.lines().map(clean_line).collect();
+    let truncated = lines.len() > MAX_LINES;
+    lines.truncate(MAX_LINES);
+    let body = Body::Text {
+        lines,
+        encoding,
+        syntax: None,
+    };
+    (body, truncated)
+}
+
+/// Read up to `READ_LIMIT` bytes of a regular file, with its length; `None` for anything else
+fn read_start(path: &Path) -> io::Result<Option<(Vec<u8>, u64)>> {
+    // Follows links, so a link to a file previews the file
+    let metadata = path.metadata()?;
+    if !metadata.is_file() {
+        return Ok(None);
+    }
+    let mut bytes = Vec::new();
+    File::open(path)?.take(READ_LIMIT).read_to_end(&mut bytes)?;
+    Ok(Some((bytes, metadata.len())))
+}
+
+/// Decode text by its byte order mark, as UTF-8, or as Latin-1; `None` if it looks binary
+fn decode_text(bytes: &[u8]) -> Option<(String, Encoding)> {
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        return Some((String::from_utf8_lossy(rest).into_owned(), Encoding::Utf8));
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        return Some((utf16(rest, u16::from_le_bytes), Encoding::Utf16Le));
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        return Some((utf16(rest, u16::from_be_bytes), Encoding::Utf16Be));
+    }
+    let sniff = &bytes[..bytes.len().min(SNIFF_LEN)];
+    if looks_binary(sniff) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some((text.to_string(), Encoding::Utf8)),
+        // Cut off mid-character by the read limit: keep the valid part
+        Err(err) if err.error_len().is_none() => {
+            let text = String::from_utf8_lossy(&bytes[..err.valid_up_to()]);
+            Some((text.into_owned(), Encoding::Utf8))
+        }
+        Err(_) => Some((bytes.iter().map(|&b| b as char).collect(), Encoding::Latin1)),
+    }
+}
+
+fn utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| from_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// A NUL byte, or more than one control character in ten, means binary
+fn looks_binary(bytes: &[u8]) -> bool {
+    let control = bytes
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+        .count();
+    bytes.contains(&0) || control * 10 > bytes.len()
+}
+
+/// Expand tabs and replace control characters, which would garble the terminal
+fn clean_line(line: &str) -> String {
+    let mut cleaned = String::with_capacity(line.len());
+    for c in line.chars() {
+        match c {
+            '\t' => {
+                let pad = 4 - cleaned.chars().count() % 4;
+                cleaned.extend(std::iter::repeat_n(' ', pad));
+            }
+            c if c.is_control() => cleaned.push('·'),
+            c => cleaned.push(c),
+        }
+    }
+    cleaned
+}
+
+/// Bytes per hex dump row that fit in `width` columns, a multiple of four
+pub fn hex_row_len(width: usize) -> usize {
+    // "00000000  " offset, then three columns per byte in hex and one as text
+    let bytes = width.saturating_sub(11) / 4;
+    (bytes / 4 * 4).clamp(4, 16)
+}
+
+/// One hex dump row: offset, bytes in hex, then the printable ones as text
+pub fn hex_row(offset: usize, bytes: &[u8], row_len: usize) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let text: String = bytes
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+    format!(
+        "{:08x}  {:<width$} {}",
+        offset,
+        hex.join(" "),
+        text,
+        width = row_len * 3 - 1
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_is_detected() {
+        assert_eq!(decode_text(b"ELF\0\x01\x02"), None);
+        assert_eq!(decode_text(b"\x01\x02\x03\x04 mostly control"), None);
+        // Tabs, line breaks, form feeds and escapes are text
+        let text = "a\tb\r\n\x0Cc \x1B[1mbold\x1B[0m\n";
+        assert_eq!(
+            decode_text(text.as_bytes()),
+            Some((text.to_string(), Encoding::Utf8))
+        );
+        // Only the start is sniffed
+        let mut late = vec![b'a'; SNIFF_LEN];
+        late.push(0);
+        assert!(decode_text(&late).is_some());
+    }
+
+    #[test]
+    fn byte_order_marks_pick_the_encoding() {
+        let le: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("hé\0".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        assert_eq!(
+            decode_text(&le),
+            Some(("hé\0".to_string(), Encoding::Utf16Le))
+        );
+        let be: Vec<u8> = [0xFE, 0xFF]
+            .into_iter()
+            .chain("hé".encode_utf16().flat_map(u16::to_be_bytes))
+            .collect();
+        assert_eq!(
+            decode_text(&be),
+            Some(("hé".to_string(), Encoding::Utf16Be))
+        );
+        assert_eq!(
+            decode_text(b"\xEF\xBB\xBFbom"),
+            Some(("bom".to_string(), Encoding::Utf8))
+        );
+    }
+
+    #[test]
+    fn invalid_utf8_falls_back_to_latin1() {
+        assert_eq!(
+            decode_text(b"caf\xE9 ok"),
+            Some(("café ok".to_string(), Encoding::Latin1))
+        );
+    }
+
+    #[test]
+    fn characters_cut_by_the_read_limit_are_dropped() {
+        let bytes = "naïve €".as_bytes();
+        let cut = &bytes[..bytes.len() - 1];
+        assert_eq!(
+            decode_text(cut),
+            Some(("naïve ".to_string(), Encoding::Utf8))
+        );
+    }
+
+    #[test]
+    fn lines_past_the_limit_are_dropped() {
+        let text = "line\n".repeat(MAX_LINES + 1);
+        let (body, truncated) = decode(text.into_bytes());
+        assert!(truncated);
+        assert!(matches!(body, Body::Text { lines, .. } if lines.len() == MAX_LINES));
+
+        let (_, truncated) = decode("line\n".repeat(MAX_LINES).into_bytes());
+        assert!(!truncated);
+    }
+}
@@ -0,0 +1,114 @@
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use planter::FileNode;
+use std::path::{Path, PathBuf};
+
+/// A folder matching the finder query
+pub struct FinderHit {
+    pub node: usize, // Index into `Tree::nodes`
+    pub score: i64,
+    pub positions: Vec<usize>, // Matched character positions in the relative path
+}
+
+/// State of the Ctrl-P "jump to folder" overlay
+pub struct Finder {
+    pub query: String,
+    pub hits: Vec<FinderHit>,
+    pub selected: usize, // Position of the highlighted hit
+    pub paths: Vec<String>, // Root-relative path of every node, indexed like `Tree::nodes`
+    files: bool, // Offer files as well as folders
+    matcher: SkimMatcherV2,
+}
+
+impl Finder {
+    pub fn new(nodes: &[FileNode], roots: &[PathBuf], files: bool) -> Self {
+        let mut finder = Finder {
+            query: String::new(),
+            hits: Vec::new(),
+            selected: 0,
+            paths: Vec::new(),
+            files,
+            matcher: SkimMatcherV2::default().smart_case(),
+        };
+        finder.update(nodes, roots);
+        finder
+    }
+
+    /// Rank every folder (and file, if offered) against the query; picks up
+    /// nodes added since the last call
+    pub fn update(&mut self, nodes: &[FileNode], roots: &[PathBuf]) {
+        for node in &nodes[self.paths.len().min(nodes.len())..] {
+            // Paths are relative to the node's own root, which is named when there are several
+            let mut root = node;
+            while let Some(parent) = root.parent {
+                root = &nodes[parent];
+            }
+            let relative = node.path.strip_prefix(&root.path).unwrap_or(&node.path);
+            let path = if roots.len() > 1 {
+                let name = if root.name.is_empty() {
+                    root.path.as_path()
+                } else {
+                    Path::new(&root.name)
+                };
+                name.join(relative)
+            } else {
+                relative.to_path_buf()
+            };
+            let path = path.to_string_lossy().to_string();
+            self.paths.push(if path.is_empty() { ".".to_string() } else { path });
+        }
+
+        let offered = |node: usize| self.files || nodes[node].is_dir;
+        self.hits = if self.query.is_empty() {
+            // Nothing typed yet: list folders in tree order
+            (0..self.paths.len())
+                .filter(|&node| offered(node))
+                .map(|node| FinderHit {
+                    node,
+                    score: 0,
+                    positions: Vec::new(),
+                })
+                .collect()
+        } else {
+            self.paths
+                .iter()
+                .enumerate()
+                .filter(|&(node, _)| offered(node))
+                .filter_map(|(node, path)| {
+                    self.matcher
+                        .fuzzy_indices(path, &self.query)
+                        .map(|(score, positions)| FinderHit {
+                            node,
+                            score,
+                            positions,
+                        })
+                })
+                .collect()
+        };
+
+        if !self.query.is_empty() {
+            // Best score first, then shorter paths, then tree order
+            let paths = &self.paths;
+            self.hits.sort_by(|a, b| {
+                b.score
+                    .cmp(&a.score)
+                    .then(paths[a.node].len().cmp(&paths[b.node].len()))
+                    .then(a.node.cmp(&b.node))
+            });
+        }
+        self.selected = self.selected.min(self.hits.len().saturating_sub(1));
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        if self.hits.is_empty() {
+            return;
+        }
+        self.selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(self.hits.len() - 1);
+    }
+
+    pub fn selected_node(&self) -> Option<usize> {
+        self.hits.get(self.selected).map(|hit| hit.node)
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        OnceLock,
+    },
+    thread,
+};
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet};
+
+/// Kinds of source text that are colored, each mapped to a theme color
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token {
+    Comment,
+    String,
+    Constant, // Numbers, `true`, `null` and the like
+    Keyword,
+    Function,
+    Type,
+}
+
+/// Colored byte ranges of one line; text outside them is plain
+pub type LineTokens = Vec<(Range<usize>, Token)>;
+
+/// The detected language of a previewed file and its colored ranges
+pub struct Highlighted {
+    pub language: &'static str,
+    pub lines: Vec<LineTokens>,
+}
+
+/// Scope prefixes and what they are colored as; the innermost matching scope wins
+const RULES: [(&str, Token); 14] = [
+    ("comment", Token::Comment),
+    ("string", Token::String),
+    ("constant.character", Token::String),
+    ("constant.numeric", Token::Constant),
+    ("constant.language", Token::Constant),
+    ("keyword", Token::Keyword),
+    ("storage", Token::Keyword),
+    ("entity.name.function", Token::Function),
+    ("support.function", Token::Function),
+    ("variable.function", Token::Function),
+    ("entity.name.type", Token::Type),
+    ("entity.name.class", Token::Type),
+    ("entity.name.tag", Token::Type),
+    ("support.type", Token::Type),
+];
+
+/// The bundled grammars, loaded on first use
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_nonewlines)
+}
+
+fn rules() -> &'static [(Scope, Token)] {
+    static SCOPES: OnceLock<Vec<(Scope, Token)>> = OnceLock::new();
+    SCOPES.get_or_init(|| {
+        RULES
+            .iter()
+            .filter_map(|&(name, token)| Some((Scope::new(name).ok()?, token)))
+            .collect()
+    })
+}
+
+/// Pick a grammar by extension, by file name (`Makefile`), or by the first line (shebangs)
+fn detect(path: &Path, first_line: &str) -> Option<&'static SyntaxReference> {
+    let syntaxes = syntaxes();
+    let by_name = |name: Option<&std::ffi::OsStr>| {
+        name.and_then(|name| syntaxes.find_syntax_by_extension(&name.to_string_lossy()))
+    };
+    by_name(path.extension())
+        .or_else(|| by_name(path.file_name()))
+        .or_else(|| syntaxes.find_syntax_by_first_line(first_line))
+        .filter(|syntax| syntax.name != "Plain Text")
+}
+
+/// Color the lines of a file, or `None` when its language isn't recognized
+pub fn highlight(path: &Path, lines: &[String]) -> Option<Highlighted> {
+    let syntax = detect(path, lines.first()?)?;
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    let mut colored = Vec::with_capacity(lines.len());
+    for line in lines {
+        // A grammar error leaves the rest of the file plain
+        let Ok(ops) = state.parse_line(line, syntaxes()) else {
+            break;
+        };
+        let mut tokens = LineTokens::new();
+        let mut start = 0;
+        for (end, op) in ops
+            .iter()
+            .map(|(pos, op)| (*pos, Some(op)))
+            .chain([(line.len(), None)])
+        {
+            if end > start {
+                if let Some(token) = token_for(&stack) {
+                    match tokens.last_mut() {
+                        Some((range, last)) if *last == token && range.end == start => {
+                            range.end = end
+                        }
+                        _ => tokens.push((start..end, token)),
+                    }
+                }
+                start = end;
+            }
+            if let Some(op) = op {
+                if stack.apply(op).is_err() {
+                    break;
+                }
+            }
+        }
+        colored.push(tokens);
+    }
+    colored.resize_with(lines.len(), LineTokens::new);
+    Some(Highlighted {
+        language: &syntax.name,
+        lines: colored,
+    })
+}
+
+/// Colors previewed files on a worker thread, so moving through files never waits on it
+pub struct Highlighter {
+    requests: Sender<(PathBuf, Vec<String>)>,
+    results: Receiver<(PathBuf, Option<Highlighted>)>,
+}
+
+impl Highlighter {
+    pub fn spawn() -> Self {
+        let (requests, inbox) = mpsc::channel::<(PathBuf, Vec<String>)>();
+        let (outbox, results) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(mut request) = inbox.recv() {
+                // Files passed over while this one was colored are no longer shown
+                while let Ok(newer) = inbox.try_recv() {
+                    request = newer;
+                }
+                let (path, lines) = request;
+                let syntax = highlight(&path, &lines);
+                if outbox.send((path, syntax)).is_err() {
+                    break;
+                }
+            }
+        });
+        Highlighter { requests, results }
+    }
+
+    pub fn request(&self, path: &Path, lines: &[String]) {
+        let _ = self.requests.send((path.to_path_buf(), lines.to_vec()));
+    }
+
+    /// The file colored last since the previous call, if any
+    pub fn try_recv(&self) -> Option<(PathBuf, Option<Highlighted>)> {
+        self.results.try_iter().last()
+    }
+}
+
+fn token_for(stack: &ScopeStack) -> Option<Token> {
+    stack.as_slice().iter().rev().find_map(|&scope| {
+        rules()
+            .iter()
+            .find(|(prefix, _)| prefix.is_prefix_of(scope))
+            .map(|&(_, token)| token)
+    })
+}
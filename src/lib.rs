@@ -0,0 +1,19 @@
+//! Headless directory scanning for planter.
+//!
+//! The [`Scanner`] walks a directory and produces a [`Tree`] of [`FileNode`]s
+//! together with [`Stats`], including the file timeline histogram. Nothing in
+//! this crate touches the terminal, so it can be embedded in other tools.
+//! [`Scanner::spawn`] runs the walk on a worker thread and streams
+//! [`ScanEvent`]s for callers that want to show progress.
+//!
+//! ```no_run
+//! let tree = planter::Scanner::new("/tmp").scan()?;
+//! println!("{} folders, {} files", tree.stats.total_dirs, tree.stats.total_files);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+pub mod scan;
+pub mod stats;
+
+pub use scan::{FileEntry, FileNode, LinkTarget, ScanError, ScanEvent, Scanner, Tree};
+pub use stats::{Stats, TimelineSource, TIMELINE_BUCKETS};
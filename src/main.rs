@@ -1,477 +1,100 @@
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, MouseButton, MouseEventKind,
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseButton,
+        MouseEventKind,
     },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
-    Frame, Terminal,
-};
+use clap::{ColorChoice, Parser};
+use planter::ScanEvent;
+use ratatui::{backend::CrosstermBackend, layout::Rect, Terminal};
 use std::{
     error::Error,
-    fs, io,
-    path::PathBuf,
-    time::{Duration, Instant, SystemTime},
+    io::{self, IsTerminal, Write},
+    sync::mpsc::{Receiver, TryRecvError},
+    time::{Duration, Instant},
 };
-use walkdir::WalkDir;
-use chrono::{DateTime, Local};
-
-// Icon constants
-const ICON_ROOT: &str = ""; // nf-fa-seedling
-const ICON_FOLDER: &str = ""; // nf-fa-folder
-const ICON_FILE: &str = ""; // file icon
-const ICON_TREE_COMPLETE: &str = ""; // nf-fa-tree
-const ICON_SPINNER: &str = ""; // nf-fa-spinner
-
-// Bar graph constants
-const TIMELINE_BAR_WIDTH: usize = 6; // Maximum bar width in characters
-const TIMELINE_BUCKETS: usize = 8; // Number of time buckets for timeline histogram
-
-#[derive(Clone)]
-struct FileNode {
-    path: PathBuf,
-    name: String,
-    is_dir: bool,
-    depth: usize,
-    #[allow(dead_code)]
-    size: u64,
-    #[allow(dead_code)]
-    children_count: usize,
-    is_last_child: bool,
-}
-
-struct Stats {
-    total_files: usize,
-    total_dirs: usize,
-    total_size: u64,
-    max_depth: usize,
-    file_timeline: Vec<usize>, // Histogram buckets counting files per time period for timeline display
-    oldest_file_time: Option<SystemTime>, // Oldest file creation time found during scan
-    newest_file_time: Option<SystemTime>, // Newest file creation time found during scan
-}
 
-struct App {
-    nodes: Vec<FileNode>,
-    animation_depth: usize, // Current depth level being animated
-    animation_complete: bool,
-    stats: Stats,
-    #[allow(dead_code)]
-    root_path: PathBuf,
-    scroll_offset: usize,
-    selected_index: Option<usize>,
-    animation_frame: usize, // For root growth animation
-    preview_contents: Vec<PreviewItem>,
-    preview_scroll_offset: usize,
-    last_click_time: Option<Instant>,
-    last_click_index: Option<usize>,
-    visible_node_indices_cache: Vec<usize>, // Cache for visible node indices
-    cache_valid: bool, // Track if cache needs refresh
-}
+mod app;
+mod cli;
+mod content;
+mod finder;
+mod highlight;
+mod report;
+mod search;
+mod sort;
+mod sunburst;
+mod theme;
+mod timeline;
+mod treemap;
+mod ui;
+
+use app::{App, View};
+use cli::{Cli, Command, Format, PrintArgs, ScanArgs, ViewArgs};
+use theme::Theme;
+use ui::{ui, Panes};
 
-#[derive(Clone)]
-struct PreviewItem {
-    name: String,
-    is_dir: bool,
-    size: u64,
-}
-
-impl App {
-    fn new(path: PathBuf) -> Result<Self, Box<dyn Error>> {
-        let mut nodes = Vec::new();
-        let mut stats = Stats {
-            total_files: 0,
-            total_dirs: 0,
-            total_size: 0,
-            max_depth: 0,
-            file_timeline: Vec::new(),
-            oldest_file_time: None,
-            newest_file_time: None,
-        };
-
-        // Collect file creation times first for timeline
-        let mut file_times: Vec<SystemTime> = Vec::new();
-        
-        // Walk the directory tree - only collect directories
-        for entry in WalkDir::new(&path)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
-            let depth = entry.depth();
-            let is_dir = path.is_dir();
-
-            // Count all items for statistics
-            if is_dir {
-                stats.total_dirs += 1;
-            } else {
-                stats.total_files += 1;
-                // Get metadata once and use for both size and creation time
-                if let Ok(metadata) = fs::metadata(path) {
-                    stats.total_size += metadata.len();
-                    
-                    // Collect creation time for timeline
-                    if let Ok(created) = metadata.created() {
-                        file_times.push(created);
-                    }
-                }
-            }
-
-            if depth > stats.max_depth {
-                stats.max_depth = depth;
-            }
-
-            // Only add directories to nodes (not files)
-            if is_dir {
-                let children_count = fs::read_dir(path)
-                    .map(|entries| entries.count())
-                    .unwrap_or(0);
-
-                nodes.push(FileNode {
-                    path: path.to_path_buf(),
-                    name: path
-                        .file_name()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                        .to_string(),
-                    is_dir,
-                    depth,
-                    size: 0,
-                    children_count,
-                    is_last_child: false, // Will be computed below
-                });
-            }
-        }
-        
-        // Build histogram from file times for a small bar chart
-        if !file_times.is_empty() {
-            file_times.sort();
-            let oldest = file_times.first().unwrap();
-            let newest = file_times.last().unwrap();
-            
-            // Store the oldest and newest times for display
-            stats.oldest_file_time = Some(*oldest);
-            stats.newest_file_time = Some(*newest);
-            
-            let time_range = newest.duration_since(*oldest).unwrap_or(Duration::from_secs(0));
-            
-            // If all files have the same timestamp, put them all in one bucket
-            if time_range.as_secs() == 0 {
-                let mut buckets = vec![0; TIMELINE_BUCKETS];
-                buckets[0] = file_times.len();
-                stats.file_timeline = buckets;
-            } else {
-                // Calculate bucket size, ensuring at least 1 second per bucket
-                let bucket_size = (time_range.as_secs() / TIMELINE_BUCKETS as u64).max(1);
-                
-                let mut buckets = vec![0; TIMELINE_BUCKETS];
-                for time in &file_times {
-                    let age = time.duration_since(*oldest).unwrap_or(Duration::from_secs(0));
-                    let bucket_idx = ((age.as_secs() / bucket_size) as usize).min(TIMELINE_BUCKETS - 1);
-                    buckets[bucket_idx] += 1;
-                }
-                stats.file_timeline = buckets;
-            }
-        } else {
-            stats.file_timeline = vec![0; TIMELINE_BUCKETS];
-        }
-
-        // Compute is_last_child for each node
-        for i in 0..nodes.len() {
-            let current_depth = nodes[i].depth;
-            let current_parent = nodes[i].path.parent();
-
-            // Check if this is the last child at its level with the same parent
-            let mut is_last = true;
-            for sibling in nodes.iter().skip(i + 1) {
-                if sibling.depth < current_depth {
-                    break; // No more siblings at this depth
-                }
-                if sibling.depth == current_depth {
-                    let sibling_parent = sibling.path.parent();
-                    if sibling_parent == current_parent {
-                        is_last = false;
-                        break;
-                    }
-                }
-            }
-            nodes[i].is_last_child = is_last;
-        }
-
-        let mut app = App {
-            nodes,
-            animation_depth: 0,
-            animation_complete: false,
-            stats,
-            root_path: path,
-            scroll_offset: 0,
-            selected_index: None,
-            animation_frame: 0,
-            preview_contents: Vec::new(),
-            preview_scroll_offset: 0,
-            last_click_time: None,
-            last_click_index: None,
-            visible_node_indices_cache: Vec::new(),
-            cache_valid: false,
-        };
-
-        // Select the first folder by default
-        if !app.nodes.is_empty() {
-            app.selected_index = Some(0);
-            app.update_preview(0);
-        }
-
-        Ok(app)
-    }
-
-    fn increment_animation(&mut self) {
-        if self.animation_depth <= self.stats.max_depth {
-            self.animation_depth += 1;
-            self.cache_valid = false; // Invalidate cache when animation progresses
-        } else {
-            self.animation_complete = true;
-        }
-        // Increment frame for smooth animation within current rendering
-        if !self.animation_complete {
-            self.animation_frame = (self.animation_frame + 1) % 3;
-        }
-    }
-
-    fn is_node_visible(&self, node: &FileNode) -> bool {
-        node.depth <= self.animation_depth
-    }
-
-    fn is_double_click(&self, idx: usize, now: Instant) -> bool {
-        if let (Some(last_time), Some(last_idx)) = (self.last_click_time, self.last_click_index) {
-            last_idx == idx && now.duration_since(last_time) < Duration::from_millis(500)
-        } else {
-            false
-        }
-    }
-
-    fn handle_mouse_click(&mut self, row: u16, area: Rect) {
-        if !self.animation_complete {
-            return;
-        }
-
-        // Calculate which item was clicked (accounting for borders and scroll)
-        if row > area.top() && row < area.bottom() - 1 {
-            let clicked_index = (row - area.top() - 1) as usize + self.scroll_offset;
-            let visible_nodes: Vec<_> = self
-                .nodes
-                .iter()
-                .filter(|n| self.is_node_visible(n))
-                .collect();
-            if clicked_index < visible_nodes.len() {
-                let node = visible_nodes[clicked_index];
-
-                // Find the actual index in the nodes vector
-                let mut actual_index = None;
-                for (idx, n) in self.nodes.iter().enumerate() {
-                    if n.path == node.path {
-                        actual_index = Some(idx);
-                        break;
-                    }
-                }
-
-                if let Some(idx) = actual_index {
-                    let now = Instant::now();
-                    let is_double_click = self.is_double_click(idx, now);
-
-                    if is_double_click {
-                        // Second click on same item - open it
-                        if node.is_dir {
-                            let _ = opener::open(&node.path);
-                        }
-                        // Reset click tracking after opening
-                        self.last_click_time = None;
-                        self.last_click_index = None;
-                    } else {
-                        // First click - select it
-                        self.selected_index = Some(idx);
-                        self.update_preview(idx);
-                        self.last_click_time = Some(now);
-                        self.last_click_index = Some(idx);
-                    }
-                }
-            }
-        }
-    }
-
-    #[allow(dead_code)]
-    fn scroll_up(&mut self) {
-        if self.scroll_offset > 0 {
-            self.scroll_offset -= 1;
-        }
-    }
-
-    #[allow(dead_code)]
-    fn scroll_down(&mut self, visible_lines: usize) {
-        let visible_count = self
-            .nodes
-            .iter()
-            .filter(|n| self.is_node_visible(n))
-            .count();
-        let max_scroll = visible_count.saturating_sub(visible_lines);
-        if self.scroll_offset < max_scroll {
-            self.scroll_offset += 1;
-        }
-    }
-
-    fn get_visible_node_indices(&mut self) -> &Vec<usize> {
-        if !self.cache_valid {
-            self.visible_node_indices_cache = self.nodes
-                .iter()
-                .enumerate()
-                .filter(|(_, n)| self.is_node_visible(n))
-                .map(|(idx, _)| idx)
-                .collect();
-            self.cache_valid = true;
-        }
-        &self.visible_node_indices_cache
-    }
-
-    fn select_previous(&mut self) {
-        // Build visible nodes cache if needed
-        let _ = self.get_visible_node_indices();
-        let visible_nodes = &self.visible_node_indices_cache;
-
-        if visible_nodes.is_empty() {
-            return;
-        }
-
-        if let Some(current) = self.selected_index {
-            // Find current position in visible nodes
-            if let Some(pos) = visible_nodes.iter().position(|&idx| idx == current) {
-                if pos > 0 {
-                    // Move to previous visible node
-                    let new_idx = visible_nodes[pos - 1];
-                    self.selected_index = Some(new_idx);
-                    self.update_preview(new_idx);
-                }
-            }
-        }
-    }
-
-    fn select_next(&mut self) {
-        // Build visible nodes cache if needed
-        let _ = self.get_visible_node_indices();
-        let visible_nodes = &self.visible_node_indices_cache;
-
-        if visible_nodes.is_empty() {
-            return;
-        }
-
-        if let Some(current) = self.selected_index {
-            // Find current position in visible nodes
-            if let Some(pos) = visible_nodes.iter().position(|&idx| idx == current) {
-                if pos < visible_nodes.len() - 1 {
-                    // Move to next visible node
-                    let new_idx = visible_nodes[pos + 1];
-                    self.selected_index = Some(new_idx);
-                    self.update_preview(new_idx);
-                }
-            }
-        }
-    }
-
-    fn ensure_selected_visible(&mut self, visible_lines: usize) {
-        if let Some(selected_idx) = self.selected_index {
-            // Build visible nodes cache if needed
-            let _ = self.get_visible_node_indices();
-            let visible_nodes = &self.visible_node_indices_cache;
-
-            if let Some(pos) = visible_nodes.iter().position(|&idx| idx == selected_idx) {
-                // Scroll up if selected is above visible area
-                if pos < self.scroll_offset {
-                    self.scroll_offset = pos;
-                }
-                // Scroll down if selected is below visible area
-                else if pos >= self.scroll_offset + visible_lines {
-                    self.scroll_offset = pos.saturating_sub(visible_lines - 1);
-                }
-            }
-        }
-    }
-
-    fn update_preview(&mut self, node_index: usize) {
-        // Clear preview first
-        self.preview_contents.clear();
-        self.preview_scroll_offset = 0;
-
-        if node_index >= self.nodes.len() {
-            return;
-        }
-
-        let node_path = &self.nodes[node_index].path;
-
-        if let Ok(entries) = fs::read_dir(node_path) {
-            let mut items: Vec<PreviewItem> = entries
-                .filter_map(|entry| entry.ok())
-                .map(|entry| {
-                    let path = entry.path();
-                    let is_dir = path.is_dir();
-                    let size = if !is_dir {
-                        fs::metadata(&path).map(|m| m.len()).unwrap_or(0)
-                    } else {
-                        0
-                    };
-                    PreviewItem {
-                        name: entry.file_name().to_string_lossy().to_string(),
-                        is_dir,
-                        size,
-                    }
-                })
-                .collect();
-
-            // Sort directories first, then files, alphabetically within each group
-            items.sort_by(|a, b| match (a.is_dir, b.is_dir) {
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                _ => a.name.cmp(&b.name),
-            });
-
-            self.preview_contents = items;
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Stats { scan, format }) => {
+            let tree = scan.scanner().scan()?;
+            print_report(|out| match format {
+                Format::Text => report::print_stats(&tree, out),
+                Format::Json => report::print_stats_json(&tree, false, out),
+                Format::Ndjson => report::print_stats_json(&tree, true, out),
+            })?;
+            report::print_errors(&tree, &mut io::stderr())?;
+            Ok(())
         }
-    }
-
-    fn scroll_preview_up(&mut self) {
-        if self.preview_scroll_offset > 0 {
-            self.preview_scroll_offset -= 1;
+        Some(Command::Tree { scan, output }) => print_tree(&scan, &output, &cli.view),
+        // Without a terminal to draw on, or when asked for printed output, print the tree
+        None if cli.print || cli.output.is_set() || !io::stdout().is_terminal() => {
+            print_tree(&cli.scan, &cli.output, &cli.view)
         }
+        None => run_tui(&cli.scan, &cli.view),
     }
+}
 
-    fn scroll_preview_down(&mut self, lines: usize) {
-        if self.preview_contents.is_empty() {
-            return;
-        }
-        let max_offset = self.preview_contents.len().saturating_sub(1);
-        self.preview_scroll_offset = (self.preview_scroll_offset + lines).min(max_offset);
+fn print_tree(scan: &ScanArgs, output: &PrintArgs, view: &ViewArgs) -> Result<(), Box<dyn Error>> {
+    let mut tree = scan.scanner().scan()?;
+    let sort = view.sort();
+    tree.sort_by(|a, b| sort.compare_nodes(a, b, view.apparent_size));
+    match output.format {
+        Format::Json => return print_report(|out| report::print_json(&tree, true, out)),
+        Format::Ndjson => return print_report(|out| report::print_json(&tree, false, out)),
+        Format::Text => {}
     }
+    let color = match output.color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => io::stdout().is_terminal(),
+    };
+    let theme = color.then(|| Theme::new(view.theme));
+    let apparent = view.apparent_size;
+    print_report(|out| report::print_tree(&tree, output, apparent, theme.as_ref(), out))?;
+    report::print_errors(&tree, &mut io::stderr())?;
+    Ok(())
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: {} <directory_path>", args[0]);
-        std::process::exit(1);
+/// Write a text report to stdout, treating a closed pipe (e.g. `planter tree | head`) as success
+fn print_report(
+    write: impl FnOnce(&mut io::StdoutLock) -> io::Result<()>,
+) -> Result<(), Box<dyn Error>> {
+    let mut out = io::stdout().lock();
+    match write(&mut out).and_then(|()| out.flush()) {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        res => Ok(res?),
     }
+}
 
-    let path = PathBuf::from(&args[1]);
-    if !path.exists() {
-        eprintln!("Error: Path '{}' does not exist", path.display());
-        std::process::exit(1);
-    }
-    if !path.is_dir() {
-        eprintln!("Error: Path '{}' is not a directory", path.display());
-        std::process::exit(1);
-    }
+fn run_tui(scan: &ScanArgs, view: &ViewArgs) -> Result<(), Box<dyn Error>> {
+    // Hidden entries are always scanned so they can be toggled at runtime.
+    // Start scanning in the background so the UI can show progress right away
+    let scanner = scan.scanner().skip_hidden(false);
+    let scan_rx = scanner.spawn()?;
 
     // Setup terminal
     enable_raw_mode()?;
@@ -481,10 +104,15 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app
-    let mut app = App::new(path)?;
+    let mut app = App::new(scanner.roots(), scan.hidden, view.files);
+    app.animate = !view.no_animation;
+    app.apparent_size = view.apparent_size;
+    app.sort = view.sort();
+    app.theme = Theme::new(view.theme);
 
     // Run app
-    let res = run_app(&mut terminal, &mut app);
+    let animation_speed = Duration::from_millis(view.speed);
+    let res = run_app(&mut terminal, &mut app, scan_rx, animation_speed);
 
     // Restore terminal
     disable_raw_mode()?;
@@ -505,75 +133,249 @@ fn main() -> Result<(), Box<dyn Error>> {
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
+    scan_rx: Receiver<ScanEvent>,
+    animation_speed: Duration, // Time between animation frames
 ) -> io::Result<()> {
-    let animation_speed = Duration::from_millis(10); // Speed of animation
     let mut last_tick = Instant::now();
 
     loop {
+        // Pull in whatever the scanner has found since the last frame
+        if app.scanning {
+            loop {
+                match scan_rx.try_recv() {
+                    Ok(event) => app.apply_scan_event(event),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        app.scanning = false;
+                        break;
+                    }
+                }
+            }
+        }
+
+        app.poll_highlighter();
+
         terminal.draw(|f| ui(f, app))?;
 
         let timeout = animation_speed.saturating_sub(last_tick.elapsed());
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
+            let event = event::read()?;
+            if let Event::Key(key) = event {
                 let area_height = terminal.size()?.height.saturating_sub(4) as usize;
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
-                    KeyCode::Up => {
-                        app.select_previous();
-                        app.ensure_selected_visible(area_height);
+                if let Some(finder) = &mut app.finder {
+                    // The finder overlay takes all keys until it is closed
+                    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                    match key.code {
+                        KeyCode::Esc => app.finder = None,
+                        KeyCode::Enter => {
+                            app.accept_finder();
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Up => finder.move_selection(-1),
+                        KeyCode::Down => finder.move_selection(1),
+                        KeyCode::Char('p') if ctrl => finder.move_selection(-1),
+                        KeyCode::Char('n') if ctrl => finder.move_selection(1),
+                        KeyCode::PageUp => finder.move_selection(-10),
+                        KeyCode::PageDown => finder.move_selection(10),
+                        KeyCode::Backspace => app.finder_pop(),
+                        KeyCode::Char(c) if !ctrl => app.finder_push(c),
+                        _ => {}
                     }
-                    KeyCode::Down => {
-                        app.select_next();
-                        app.ensure_selected_visible(area_height);
+                } else if app.errors_view.is_some() {
+                    // So does the errors overlay
+                    match key.code {
+                        KeyCode::Esc | KeyCode::Char('e') | KeyCode::Char('q') => {
+                            app.errors_view = None
+                        }
+                        KeyCode::Enter => {
+                            app.accept_error();
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Up => app.move_error_selection(-1),
+                        KeyCode::Down => app.move_error_selection(1),
+                        KeyCode::PageUp => app.move_error_selection(-10),
+                        KeyCode::PageDown => app.move_error_selection(10),
+                        _ => {}
                     }
-                    KeyCode::Left => app.scroll_preview_up(),
-                    KeyCode::Right => app.scroll_preview_down(1),
-                    KeyCode::PageUp => {
-                        for _ in 0..10 {
-                            app.select_previous();
+                } else if app.search.editing {
+                    // The search prompt takes all keys until it is closed
+                    match key.code {
+                        KeyCode::Esc => app.cancel_search(),
+                        KeyCode::Enter => app.finish_search(),
+                        KeyCode::Backspace => app.search_pop(),
+                        KeyCode::Tab => app.cycle_search_mode(),
+                        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.search_push(c)
                         }
-                        app.ensure_selected_visible(area_height);
+                        _ => {}
                     }
-                    KeyCode::PageDown => {
-                        for _ in 0..10 {
-                            app.select_next();
+                    app.ensure_selected_visible(area_height);
+                } else if app.timeline_focused && is_timeline_key(key.code) {
+                    // The timeline in the Info panel picks buckets; other keys stay global
+                    let extend = key.modifiers.contains(KeyModifiers::SHIFT);
+                    match key.code {
+                        KeyCode::Up => app.timeline.move_cursor(-1, extend),
+                        KeyCode::Down => app.timeline.move_cursor(1, extend),
+                        KeyCode::PageUp => {
+                            app.timeline.move_cursor(-(timeline::ROWS as isize), extend)
+                        }
+                        KeyCode::PageDown => {
+                            app.timeline.move_cursor(timeline::ROWS as isize, extend)
                         }
-                        app.ensure_selected_visible(area_height);
+                        KeyCode::Home => app.timeline.move_cursor(isize::MIN, extend),
+                        KeyCode::End => app.timeline.move_cursor(isize::MAX, extend),
+                        KeyCode::Enter => app.toggle_time_filter(),
+                        KeyCode::Char('+') => app.zoom_timeline(true),
+                        KeyCode::Char('-') => app.zoom_timeline(false),
+                        KeyCode::Char('g') => app.cycle_timeline_granularity(),
+                        KeyCode::Char('c') => app.cycle_timeline_field(),
+                        KeyCode::Esc => app.toggle_timeline_focus(),
+                        _ => {}
                     }
-                    KeyCode::Enter => {
-                        // Open the currently selected folder with Enter key
-                        if app.animation_complete {
-                            if let Some(idx) = app.selected_index {
-                                if let Some(node) = app.nodes.get(idx) {
-                                    if node.is_dir {
-                                        let _ = opener::open(&node.path);
-                                    }
-                                }
-                            }
+                    app.ensure_selected_visible(area_height);
+                } else if app.preview_focused && is_preview_key(key.code) {
+                    // Moving around is done in the preview; everything else stays global
+                    let size = terminal.size()?;
+                    let panes = Panes::new(Rect::new(0, 0, size.width, size.height));
+                    let preview_height = panes.preview.height.saturating_sub(2) as usize;
+                    match key.code {
+                        KeyCode::Up => app.move_preview_selection(-1, preview_height),
+                        KeyCode::Down => app.move_preview_selection(1, preview_height),
+                        KeyCode::PageUp => {
+                            app.move_preview_selection(-(preview_height as isize), preview_height)
+                        }
+                        KeyCode::PageDown => {
+                            app.move_preview_selection(preview_height as isize, preview_height)
                         }
+                        KeyCode::Home => app.move_preview_selection(isize::MIN, preview_height),
+                        KeyCode::End => app.move_preview_selection(isize::MAX, preview_height),
+                        KeyCode::Left => app.leave_preview_selection(preview_height),
+                        KeyCode::Right => app.enter_preview_selection(),
+                        KeyCode::Enter => app.open_preview_selection(),
+                        _ => {}
+                    }
+                    app.ensure_selected_visible(area_height);
+                } else if is_chart_key(app.view, key.code) {
+                    // Charts move between tiles or arcs instead of tree rows
+                    match (app.view, key.code) {
+                        (View::Treemap, KeyCode::Up) => app.move_treemap_selection(0, -1),
+                        (View::Treemap, KeyCode::Down) => app.move_treemap_selection(0, 1),
+                        (View::Treemap, KeyCode::Left) => app.move_treemap_selection(-1, 0),
+                        (View::Treemap, KeyCode::Right) => app.move_treemap_selection(1, 0),
+                        (View::Treemap, KeyCode::Char('c')) => app.cycle_treemap_color(),
+                        (_, KeyCode::Up) => app.move_sunburst_to_parent(),
+                        (_, KeyCode::Down) => app.move_sunburst_to_child(),
+                        (_, KeyCode::Left) => app.move_sunburst_around(-1),
+                        (_, KeyCode::Right) => app.move_sunburst_around(1),
+                        (_, KeyCode::Enter) => app.drill_down(),
+                        (_, KeyCode::Backspace) => app.drill_up(),
+                        _ => {}
+                    }
+                } else {
+                    match key.code {
+                        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.open_finder()
+                        }
+                        KeyCode::Esc if app.search.is_active() => app.clear_search(),
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Tab => app.toggle_preview_focus(),
+                        KeyCode::Char('t') => app.toggle_timeline_focus(),
+                        KeyCode::Char('v') => {
+                            app.cycle_view();
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Char('e') => app.open_errors(),
+                        KeyCode::Char('/') => app.start_search(false),
+                        KeyCode::Char('&') if app.search.is_active() => app.toggle_filter(),
+                        KeyCode::Char('&') => app.start_search(true),
+                        KeyCode::Char('n') => {
+                            app.next_match(true);
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Char('N') => {
+                            app.next_match(false);
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Up => {
+                            app.select_previous();
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Down => {
+                            app.select_next();
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Left => {
+                            app.collapse_selected();
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Right => {
+                            app.expand_selected();
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Char(' ') => app.toggle_selected(),
+                        KeyCode::Char('*') => app.expand_all_below(),
+                        KeyCode::Char('.') => {
+                            app.toggle_hidden();
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Char(c @ '0'..='9') => {
+                            app.collapse_to_depth(c as usize - '0' as usize);
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Char('a') => app.toggle_apparent_size(),
+                        KeyCode::Char('s') => app.cycle_sort(),
+                        KeyCode::Char('r') => app.reverse_sort(),
+                        KeyCode::Char('f') => {
+                            app.toggle_files();
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Char('[') => app.scroll_preview_up(),
+                        KeyCode::Char(']') => app.scroll_preview_down(1),
+                        KeyCode::PageUp => {
+                            app.move_selection(-(area_height as isize));
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::PageDown => {
+                            app.move_selection(area_height as isize);
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Home => {
+                            app.move_selection(isize::MIN);
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::End => {
+                            app.move_selection(isize::MAX);
+                            app.ensure_selected_visible(area_height);
+                        }
+                        KeyCode::Enter => app.open_selected(),
+                        _ => {}
                     }
-                    _ => {}
                 }
-            } else if let Event::Mouse(mouse) = event::read()? {
+            } else if let Event::Mouse(mouse) = event {
                 let area_height = terminal.size()?.height.saturating_sub(4) as usize;
                 let size = terminal.size()?;
-                let area = Rect::new(0, 0, size.width, size.height);
-                let chunks = Layout::default()
-                    .direction(Direction::Horizontal)
-                    .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
-                    .margin(0)
-                    .split(area);
-                
+                let panes = Panes::new(Rect::new(0, 0, size.width, size.height));
+
                 match mouse.kind {
                     MouseEventKind::Down(MouseButton::Left) => {
-                        app.handle_mouse_click(mouse.row, chunks[0]);
+                        if mouse.column < panes.tree.width && app.view == View::Treemap {
+                            app.handle_treemap_click(mouse.column, mouse.row, panes.tree);
+                        } else if mouse.column < panes.tree.width && app.view == View::Sunburst {
+                            app.handle_sunburst_click(mouse.column, mouse.row, panes.tree);
+                        } else if mouse.column < panes.tree.width {
+                            app.handle_mouse_click(mouse.row, panes.tree);
+                        } else if mouse.row >= panes.preview.top() {
+                            app.handle_preview_click(mouse.row, panes.preview);
+                        }
                     }
                     MouseEventKind::ScrollUp => {
                         // Check if mouse is in left panel (tree) or right panel (preview)
-                        if mouse.column < chunks[0].width {
-                            // Scroll tree up
-                            app.select_previous();
-                            app.ensure_selected_visible(area_height);
+                        if mouse.column < panes.tree.width {
+                            // Scroll tree up; charts have no order to scroll through
+                            if app.view == View::Tree {
+                                app.select_previous();
+                                app.ensure_selected_visible(area_height);
+                            }
                         } else {
                             // Scroll preview up
                             app.scroll_preview_up();
@@ -581,15 +383,20 @@ fn run_app<B: ratatui::backend::Backend>(
                     }
                     MouseEventKind::ScrollDown => {
                         // Check if mouse is in left panel (tree) or right panel (preview)
-                        if mouse.column < chunks[0].width {
-                            // Scroll tree down
-                            app.select_next();
-                            app.ensure_selected_visible(area_height);
+                        if mouse.column < panes.tree.width {
+                            // Scroll tree down; charts have no order to scroll through
+                            if app.view == View::Tree {
+                                app.select_next();
+                                app.ensure_selected_visible(area_height);
+                            }
                         } else {
                             // Scroll preview down
                             app.scroll_preview_down(1);
                         }
                     }
+                    MouseEventKind::Moved if app.view == View::Sunburst => {
+                        app.hover_sunburst(mouse.column, mouse.row);
+                    }
                     _ => {}
                 }
             }
@@ -604,358 +411,48 @@ fn run_app<B: ratatui::backend::Backend>(
     }
 }
 
-fn ui(f: &mut Frame, app: &App) {
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
-        .margin(0)
-        .split(f.area());
-
-    // Left panel: Tree view
-    render_tree(f, app, chunks[0]);
-
-    // Right panel: Split into stats and preview
-    let right_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-        .margin(0)
-        .split(chunks[1]);
-
-    // Top right: Statistics
-    render_stats(f, app, right_chunks[0]);
-
-    // Bottom right: Folder contents preview
-    render_preview(f, app, right_chunks[1]);
-}
-
-fn render_tree(f: &mut Frame, app: &App, area: Rect) {
-    let visible_height = area.height.saturating_sub(2) as usize; // Account for borders
-
-    // First, collect all visible nodes with their index in the full list
-    let all_visible: Vec<(usize, &FileNode)> = app
-        .nodes
-        .iter()
-        .enumerate()
-        .filter(|(_, n)| app.is_node_visible(n))
-        .collect();
-
-    let visible_nodes: Vec<ListItem> = all_visible
-        .iter()
-        .enumerate()
-        .skip(app.scroll_offset)
-        .take(visible_height)
-        .map(|(_list_idx, (actual_index, node))| {
-            // Build tree connectors
-            let mut tree_prefix = String::new();
-
-            if node.depth > 0 {
-                // For each depth level before the current node's depth,
-                // determine if we need to show a vertical line.
-                // Start from 0 to include the root level connection.
-                for ancestor_depth in 0..node.depth {
-                    let should_show_line = if ancestor_depth == 0 {
-                        // For column 0 (root level), check if the depth-1 ancestor
-                        // (or the node itself if at depth 1) is not the last child at depth 1.
-                        // This creates a visual connection from the root to its children.
-                        let depth_1_ancestor = node.path.ancestors().nth(node.depth - 1);
-                        app.nodes
-                            .iter()
-                            .find(|n| n.depth == 1 && Some(n.path.as_path()) == depth_1_ancestor)
-                            .map(|n| !n.is_last_child)
-                            .unwrap_or(false)
-                    } else {
-                        // For other columns, find the ancestor at this depth level
-                        let ancestor_at_level = node.path.ancestors().nth(node.depth - ancestor_depth);
-                        app.nodes
-                            .iter()
-                            .find(|n| {
-                                n.depth == ancestor_depth && 
-                                Some(n.path.as_path()) == ancestor_at_level
-                            })
-                            .map(|n| !n.is_last_child)
-                            .unwrap_or(false)
-                    };
-
-                    if should_show_line {
-                        tree_prefix.push('│');
-                        tree_prefix.push(' '); // Add spacing after the vertical line
-                    } else {
-                        // Add two spaces to maintain alignment when no vertical line is needed
-                        tree_prefix.push_str("  ");
-                    }
-                }
-
-                // Determine connector for current node
-                let base_connector = if node.is_last_child {
-                    "└─" // Last child uses corner
-                } else {
-                    "├─" // Not last child uses tee
-                };
-
-                // Animation effect: show growing roots
-                if !app.animation_complete && node.depth == app.animation_depth {
-                    let prefix = if node.is_last_child { "└" } else { "├" };
-                    match app.animation_frame % 3 {
-                        0 => tree_prefix.push_str(prefix),
-                        1 => tree_prefix.push_str(&format!("{}─", prefix)),
-                        _ => tree_prefix.push_str(base_connector),
-                    }
-                } else {
-                    tree_prefix.push_str(base_connector);
-                }
-            }
-
-            // Use Nerd Font icons instead of emojis
-            let icon = if node.depth == 0 {
-                ICON_ROOT
-            } else {
-                ICON_FOLDER
-            };
-
-            let display_name = if node.name.is_empty() {
-                node.path.to_string_lossy().to_string()
-            } else {
-                node.name.clone()
-            };
-
-            let mut style = Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD);
-
-            if app.selected_index == Some(*actual_index) {
-                style = style.bg(Color::DarkGray);
-            }
-
-            // Color the tree connectors differently
-            let connector_style = Style::default().fg(Color::Green);
-            let icon_style = style;
-
-            let line = Line::from(vec![
-                Span::styled(tree_prefix, connector_style),
-                Span::styled(format!("{} {}", icon, display_name), icon_style),
-            ]);
-
-            ListItem::new(line)
-        })
-        .collect();
-
-    let visible_count = app.nodes.iter().filter(|n| app.is_node_visible(n)).count();
-    let title = format!(
-        " {} ({}/{}) - Depth {}/{} ",
-        if app.animation_complete {
-            ICON_TREE_COMPLETE
-        } else {
-            ICON_SPINNER
-        },
-        visible_count,
-        app.nodes.len(),
-        app.animation_depth,
-        app.stats.max_depth
-    );
-
-    let list = List::new(visible_nodes).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title(title)
-            .style(Style::default().fg(Color::Green)),
-    );
-
-    f.render_widget(list, area);
-}
-
-/// Format a SystemTime as a date string with a label.
-///
-/// # Arguments
-/// * `time` - The SystemTime to format
-/// * `label` - A label to append to the formatted date (e.g., "newest", "oldest")
-///
-/// # Returns
-/// Returns Some(String) containing the formatted date with label.
-/// Currently always returns Some, but uses Option for future error handling flexibility.
-fn format_system_time(time: SystemTime, label: &str) -> Option<String> {
-    let datetime: DateTime<Local> = time.into();
-    Some(format!(" {} ({})", datetime.format("%Y-%m-%d"), label))
-}
-
-fn render_stats(f: &mut Frame, app: &App, area: Rect) {
-    let mut stats_text = vec![
-        Line::from(vec![Span::styled(
-            " Statistics",
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from(vec![
-            Span::styled(" Folders: ", Style::default().add_modifier(Modifier::BOLD)),
-            Span::styled(
-                format!("{}", app.stats.total_dirs),
-                Style::default().fg(Color::Cyan),
-            ),
-        ]),
-        Line::from(vec![
-            Span::styled(" Files: ", Style::default().add_modifier(Modifier::BOLD)),
-            Span::styled(
-                format!("{}", app.stats.total_files),
-                Style::default().fg(Color::Green),
-            ),
-        ]),
-        Line::from(vec![
-            Span::styled(
-                " Total Size: ",
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(
-                humansize::format_size(app.stats.total_size, humansize::BINARY),
-                Style::default().fg(Color::Yellow),
-            ),
-        ]),
-        Line::from(vec![
-            Span::styled(
-                " Max Depth: ",
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(
-                format!("{}", app.stats.max_depth),
-                Style::default().fg(Color::Blue),
-            ),
-        ]),
-    ];
-    
-    // Add file timeline bar graph
-    if !app.stats.file_timeline.is_empty() && app.stats.file_timeline.iter().sum::<usize>() > 0 {
-        stats_text.push(Line::from(vec![Span::styled(
-            " File Timeline:",
-            Style::default().add_modifier(Modifier::BOLD),
-        )]));
-        
-        // Display newest file creation date at the top
-        if let Some(newest_time) = app.stats.newest_file_time {
-            if let Some(date_str) = format_system_time(newest_time, "newest") {
-                stats_text.push(Line::from(vec![Span::styled(
-                    date_str,
-                    Style::default().fg(Color::DarkGray),
-                )]));
-            }
-        }
-        
-        let max_count = *app.stats.file_timeline.iter().max().unwrap_or(&1);
-        
-        for &count in &app.stats.file_timeline {
-            if count > 0 {
-                // Use integer arithmetic for ceiling division: (a + b - 1) / b
-                let bar_len = (count * TIMELINE_BAR_WIDTH + max_count - 1) / max_count;
-                let bar = "█".repeat(bar_len);
-                stats_text.push(Line::from(vec![
-                    Span::raw(" "),
-                    Span::styled(bar, Style::default().fg(Color::Green)),
-                    Span::styled(format!(" {}", count), Style::default().fg(Color::DarkGray)),
-                ]));
-            }
-        }
-        
-        // Display oldest file date at the bottom
-        if let Some(oldest_time) = app.stats.oldest_file_time {
-            if let Some(date_str) = format_system_time(oldest_time, "oldest") {
-                stats_text.push(Line::from(vec![Span::styled(
-                    date_str,
-                    Style::default().fg(Color::DarkGray),
-                )]));
-            }
-        }
+/// Keys that act on a chart while it replaces the tree
+fn is_chart_key(view: View, code: KeyCode) -> bool {
+    match code {
+        KeyCode::Up
+        | KeyCode::Down
+        | KeyCode::Left
+        | KeyCode::Right
+        | KeyCode::Enter
+        | KeyCode::Backspace => view != View::Tree,
+        KeyCode::Char('c') => view == View::Treemap, // Colors are only for tiles
+        _ => false,
     }
-    
-    stats_text.extend(vec![
-        Line::from(vec![Span::styled(
-            " Controls:",
-            Style::default()
-                .fg(Color::White)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from(vec![Span::raw(" ↑/↓ - Navigate selection")]),
-        Line::from(vec![Span::raw(" ←/→ - Scroll preview")]),
-        Line::from(vec![Span::raw(" Wheel - Scroll panels")]),
-        if app.animation_complete {
-            Line::from(vec![Span::styled(
-                " Click - Select/Open",
-                Style::default().fg(Color::Green),
-            )])
-        } else {
-            Line::from(vec![Span::styled(
-                " Wait for animation...",
-                Style::default().fg(Color::DarkGray),
-            )])
-        },
-        Line::from(vec![Span::raw(" Q/Esc - Quit")]),
-    ]);
-
-    let paragraph = Paragraph::new(stats_text).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title("  Info ")
-            .style(Style::default().fg(Color::Green)),
-    );
-
-    f.render_widget(paragraph, area);
 }
 
-fn render_preview(f: &mut Frame, app: &App, area: Rect) {
-    let visible_height = area.height.saturating_sub(2) as usize;
-
-    let preview_items: Vec<ListItem> = app
-        .preview_contents
-        .iter()
-        .skip(app.preview_scroll_offset)
-        .take(visible_height)
-        .map(|item| {
-            let icon = if item.is_dir { ICON_FOLDER } else { ICON_FILE };
-
-            let size_str = if item.is_dir {
-                String::new()
-            } else {
-                format!(
-                    " ({})",
-                    humansize::format_size(item.size, humansize::BINARY)
-                )
-            };
-
-            let style = if item.is_dir {
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD)
-            } else {
-                Style::default().fg(Color::White)
-            };
-
-            let line = Line::from(vec![Span::styled(
-                format!(" {} {}{}", icon, item.name, size_str),
-                style,
-            )]);
-
-            ListItem::new(line)
-        })
-        .collect();
-
-    let title = if let Some(idx) = app.selected_index {
-        if let Some(node) = app.nodes.get(idx) {
-            format!(
-                " {} {} ({} items) ",
-                ICON_FOLDER,
-                node.name,
-                app.preview_contents.len()
-            )
-        } else {
-            format!(" {} Folder Contents ", ICON_FOLDER)
-        }
-    } else {
-        format!(" {} Folder Contents (Click to select) ", ICON_FOLDER)
-    };
-
-    let list = List::new(preview_items).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title(title)
-            .style(Style::default().fg(Color::Green)),
-    );
+/// Keys that act on the timeline while it has the focus
+fn is_timeline_key(code: KeyCode) -> bool {
+    matches!(
+        code,
+        KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+            | KeyCode::Home
+            | KeyCode::End
+            | KeyCode::Enter
+            | KeyCode::Esc
+            | KeyCode::Char('+' | '-' | 'g' | 'c')
+    )
+}
 
-    f.render_widget(list, area);
+/// Keys that act on the preview pane while it has the focus
+fn is_preview_key(code: KeyCode) -> bool {
+    matches!(
+        code,
+        KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+            | KeyCode::Home
+            | KeyCode::End
+            | KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Enter
+    )
 }
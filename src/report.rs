@@ -0,0 +1,478 @@
+use crate::cli::PrintArgs;
+use crate::theme::Theme;
+use crate::ui::{format_system_time, link_label, node_icon, tree_indent};
+use chrono::{DateTime, SecondsFormat, Utc};
+use crossterm::style::Stylize;
+use planter::{FileNode, LinkTarget, ScanError, Stats, TimelineSource, Tree};
+use serde::{ser::Serializer, Serialize};
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+    time::SystemTime,
+};
+
+const TIMELINE_BAR_WIDTH: usize = 30; // Maximum bar width in characters
+
+/// Print the totals and the file timeline, like the Info panel shows them
+pub fn print_stats(tree: &Tree, out: &mut impl Write) -> io::Result<()> {
+    let stats = &tree.stats;
+    writeln!(out, "Folders: {}", stats.total_dirs)?;
+    writeln!(out, "Files: {}", stats.total_files)?;
+    writeln!(
+        out,
+        "Disk Usage: {}",
+        humansize::format_size(stats.total_disk_size, humansize::BINARY)
+    )?;
+    writeln!(
+        out,
+        "Apparent Size: {}",
+        humansize::format_size(stats.total_size, humansize::BINARY)
+    )?;
+    if stats.hard_links > 0 {
+        writeln!(out, "Hard Links: {} counted once", stats.hard_links)?;
+    }
+    writeln!(out, "Max Depth: {}", stats.max_depth)?;
+    if !tree.errors.is_empty() {
+        writeln!(out, "Errors: {}", tree.errors.len())?;
+    }
+    if !tree.skipped_filesystems.is_empty() {
+        writeln!(out, "Skipped Filesystems:")?;
+        for path in &tree.skipped_filesystems {
+            writeln!(out, " {}", path.display())?;
+        }
+    }
+    if stats.broken_links > 0 {
+        writeln!(out, "Broken Links: {}", stats.broken_links)?;
+    }
+    if stats.skipped_entries > 0 {
+        writeln!(out, "Skipped: {}", stats.skipped_entries)?;
+    }
+
+    let max_count = stats.file_timeline.iter().copied().max().unwrap_or(0);
+    if max_count == 0 {
+        return Ok(());
+    }
+    // Say so when creation times were missing and modification times were counted instead
+    match stats.timeline_source {
+        TimelineSource::Created => writeln!(out, "File Timeline:")?,
+        source => writeln!(out, "File Timeline ({}):", source.label())?,
+    }
+    if let Some(date) = stats.newest_file_time.and_then(|t| format_system_time(t, "newest")) {
+        writeln!(out, "{}", date)?;
+    }
+    // Buckets run from oldest to newest; list them newest first, under their label
+    for &count in stats.file_timeline.iter().rev().filter(|&&count| count > 0) {
+        let bar_len = (count * TIMELINE_BAR_WIDTH).div_ceil(max_count);
+        writeln!(out, " {} {}", "█".repeat(bar_len), count)?;
+    }
+    if let Some(date) = stats.oldest_file_time.and_then(|t| format_system_time(t, "oldest")) {
+        writeln!(out, "{}", date)?;
+    }
+    Ok(())
+}
+
+/// Print the folder tree with the same connectors as the interactive view.
+///
+/// Sizes are the space used on disk unless `apparent`. With a theme the
+/// output is colored with ANSI escape codes. Folders with scan errors are
+/// marked with a warning sign; see [`print_errors`].
+pub fn print_tree(
+    tree: &Tree,
+    args: &PrintArgs,
+    apparent: bool,
+    theme: Option<&Theme>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    for (idx, node) in tree.nodes.iter().enumerate() {
+        let mut prefix = String::new();
+        if node.depth > 0 {
+            prefix.push_str(&tree_indent(&tree.nodes, idx, false));
+            prefix.push_str(if node.is_last_child { "└─" } else { "├─" });
+        }
+        let icon = node_icon(node);
+        let name = if node.name.is_empty() {
+            node.path.to_string_lossy().to_string()
+        } else {
+            node.name.clone()
+        };
+
+        let mut details = Vec::new();
+        if args.sizes {
+            let size = node.usage(apparent);
+            details.push(humansize::format_size(size, humansize::BINARY));
+        }
+        if args.counts {
+            details.push(count(node.file_count, "file"));
+            details.push(count(node.dir_count, "dir"));
+        }
+        let details = if details.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", details.join(", "))
+        };
+        let warning = if node.error.is_some() { " ⚠" } else { "" };
+        let link = node.link.as_ref().map(link_label).unwrap_or_default();
+        let broken = match &node.link {
+            Some(link) if link.broken => " (broken)",
+            _ => "",
+        };
+
+        match theme {
+            Some(theme) => writeln!(
+                out,
+                "{}{} {}{}{}{}{}",
+                prefix.with(theme.border.into()),
+                icon.with(theme.folder.into()),
+                name.with(theme.folder.into()).bold(),
+                link.with(theme.muted.into()),
+                broken.with(theme.error.into()),
+                warning.with(theme.error.into()),
+                details.with(theme.muted.into())
+            )?,
+            None => writeln!(
+                out,
+                "{}{} {}{}{}{}{}",
+                prefix, icon, name, link, broken, warning, details
+            )?,
+        }
+    }
+    Ok(())
+}
+
+/// A number with its noun, in the plural unless there is one
+fn count(n: usize, noun: &str) -> String {
+    match n {
+        1 => format!("1 {}", noun),
+        n => format!("{} {}s", n, noun),
+    }
+}
+
+/// List the paths that could not be read, one per line, like `du` does
+pub fn print_errors(tree: &Tree, out: &mut impl Write) -> io::Result<()> {
+    for error in &tree.errors {
+        writeln!(
+            out,
+            "planter: cannot read '{}': {}",
+            error.path.display(),
+            error.message
+        )?;
+    }
+    Ok(())
+}
+
+/// Statistics as they appear in JSON output
+#[derive(Serialize)]
+struct StatsJson<'a> {
+    total_files: usize,
+    total_dirs: usize,
+    total_size: u64,
+    total_disk_size: u64,
+    hard_links: usize,
+    max_depth: usize,
+    skipped_entries: usize,
+    broken_links: usize,
+    file_timeline: &'a [usize], // Oldest bucket first
+    timeline_source: &'static str, // Which file times the timeline counts
+    oldest_file_time: Option<String>,
+    newest_file_time: Option<String>,
+}
+
+impl<'a> StatsJson<'a> {
+    fn new(stats: &'a Stats) -> Self {
+        StatsJson {
+            total_files: stats.total_files,
+            total_dirs: stats.total_dirs,
+            total_size: stats.total_size,
+            total_disk_size: stats.total_disk_size,
+            hard_links: stats.hard_links,
+            max_depth: stats.max_depth,
+            skipped_entries: stats.skipped_entries,
+            broken_links: stats.broken_links,
+            file_timeline: &stats.file_timeline,
+            timeline_source: stats.timeline_source.label(),
+            oldest_file_time: stats.oldest_file_time.map(timestamp),
+            newest_file_time: stats.newest_file_time.map(timestamp),
+        }
+    }
+}
+
+/// The fields shared by both JSON layouts
+#[derive(Serialize)]
+struct NodeJson<'a> {
+    path: Cow<'a, str>,
+    name: &'a str,
+    depth: usize,
+    size: u64,
+    disk_size: u64,
+    file_count: usize,
+    dir_count: usize,
+    children_count: usize,
+    created: Option<String>, // `null` where the filesystem records no creation time
+    modified: Option<String>,
+    accessed: Option<String>,
+    error: Option<String>, // `io::ErrorKind` of the first error in this folder
+    mount_point: bool,
+    link: Option<LinkJson<'a>>, // Set for symbolic links
+}
+
+#[derive(Serialize)]
+struct LinkJson<'a> {
+    target: Cow<'a, str>,
+    broken: bool,
+}
+
+impl<'a> LinkJson<'a> {
+    fn new(link: &'a LinkTarget) -> Self {
+        LinkJson {
+            target: link.path.to_string_lossy(),
+            broken: link.broken,
+        }
+    }
+}
+
+impl<'a> NodeJson<'a> {
+    fn new(node: &'a FileNode) -> Self {
+        NodeJson {
+            path: node.path.to_string_lossy(),
+            name: &node.name,
+            depth: node.depth,
+            size: node.size,
+            disk_size: node.disk_size,
+            file_count: node.file_count,
+            dir_count: node.dir_count,
+            children_count: node.children_count,
+            created: node.created.map(timestamp),
+            modified: node.modified.map(timestamp),
+            accessed: node.accessed.map(timestamp),
+            error: node.error.map(|kind| format!("{:?}", kind)),
+            mount_point: node.mount_point,
+            link: node.link.as_ref().map(LinkJson::new),
+        }
+    }
+}
+
+/// A folder with its subfolders, serialized recursively straight from the tree
+#[derive(Serialize)]
+struct NestedNode<'a> {
+    #[serde(flatten)]
+    node: NodeJson<'a>,
+    children: Children<'a>,
+}
+
+impl<'a> NestedNode<'a> {
+    fn new(tree: &'a Tree, children: &'a [Vec<usize>], idx: usize) -> Self {
+        NestedNode {
+            node: NodeJson::new(&tree.nodes[idx]),
+            children: Children {
+                tree,
+                children,
+                idx,
+            },
+        }
+    }
+}
+
+/// The subfolders of node `idx`; `children` lists them for every node
+struct Children<'a> {
+    tree: &'a Tree,
+    children: &'a [Vec<usize>],
+    idx: usize,
+}
+
+impl Serialize for Children<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            self.children[self.idx]
+                .iter()
+                .map(|&child| NestedNode::new(self.tree, self.children, child)),
+        )
+    }
+}
+
+/// A path that could not be read
+#[derive(Serialize)]
+struct ErrorJson<'a> {
+    path: Cow<'a, str>,
+    kind: String, // `io::ErrorKind`, e.g. `PermissionDenied`
+    message: &'a str,
+}
+
+impl<'a> ErrorJson<'a> {
+    fn new(error: &'a ScanError) -> Self {
+        ErrorJson {
+            path: error.path.to_string_lossy(),
+            kind: format!("{:?}", error.kind),
+            message: &error.message,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct NestedDocument<'a> {
+    roots: Vec<NestedNode<'a>>,
+    errors: Vec<ErrorJson<'a>>,
+    skipped_filesystems: Vec<Cow<'a, str>>,
+    stats: StatsJson<'a>,
+}
+
+/// One line of NDJSON output
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Record<'a> {
+    Node {
+        index: usize, // Position in depth-first order
+        parent: Option<usize>, // `index` of the parent folder
+        #[serde(flatten)]
+        node: NodeJson<'a>,
+    },
+    Error(ErrorJson<'a>),
+    #[serde(rename = "skipped_filesystem")]
+    SkippedFilesystem { path: Cow<'a, str> },
+    Stats(StatsJson<'a>),
+}
+
+/// Print the tree and statistics as JSON.
+///
+/// The nested layout is a single document; the flat one writes a line per
+/// folder in depth-first order, then a line per error and per skipped
+/// filesystem, then a line with the statistics.
+pub fn print_json(tree: &Tree, nested: bool, out: &mut impl Write) -> io::Result<()> {
+    if nested {
+        let mut children = vec![Vec::new(); tree.nodes.len()];
+        let mut roots = Vec::new();
+        for (idx, node) in tree.nodes.iter().enumerate() {
+            match node.parent {
+                Some(parent) => children[parent].push(idx),
+                None => roots.push(idx),
+            }
+        }
+        let document = NestedDocument {
+            roots: roots
+                .into_iter()
+                .map(|idx| NestedNode::new(tree, &children, idx))
+                .collect(),
+            errors: tree.errors.iter().map(ErrorJson::new).collect(),
+            skipped_filesystems: tree
+                .skipped_filesystems
+                .iter()
+                .map(|path| path.to_string_lossy())
+                .collect(),
+            stats: StatsJson::new(&tree.stats),
+        };
+        serde_json::to_writer_pretty(&mut *out, &document)?;
+        writeln!(out)?;
+    } else {
+        for (index, node) in tree.nodes.iter().enumerate() {
+            let record = Record::Node {
+                index,
+                parent: node.parent,
+                node: NodeJson::new(node),
+            };
+            serde_json::to_writer(&mut *out, &record)?;
+            writeln!(out)?;
+        }
+        for error in &tree.errors {
+            serde_json::to_writer(&mut *out, &Record::Error(ErrorJson::new(error)))?;
+            writeln!(out)?;
+        }
+        for path in &tree.skipped_filesystems {
+            let record = Record::SkippedFilesystem {
+                path: path.to_string_lossy(),
+            };
+            serde_json::to_writer(&mut *out, &record)?;
+            writeln!(out)?;
+        }
+        serde_json::to_writer(&mut *out, &Record::Stats(StatsJson::new(&tree.stats)))?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Print only the statistics as a JSON object, pretty-printed unless `compact`
+pub fn print_stats_json(tree: &Tree, compact: bool, out: &mut impl Write) -> io::Result<()> {
+    let stats = StatsJson::new(&tree.stats);
+    if compact {
+        serde_json::to_writer(&mut *out, &stats)?;
+    } else {
+        serde_json::to_writer_pretty(&mut *out, &stats)?;
+    }
+    writeln!(out)
+}
+
+/// RFC 3339 time in UTC, e.g. `2024-05-01T12:00:00Z`
+fn timestamp(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Format;
+    use clap::ColorChoice;
+    use planter::ScanEvent;
+    use std::path::PathBuf;
+
+    /// A folder (`size` none) or file below the root `r`, as a scan reports it
+    fn entry(path: &str, size: Option<u64>) -> FileNode {
+        let path = PathBuf::from(path);
+        FileNode {
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            is_dir: size.is_none(),
+            depth: path.components().count() - 1,
+            size: size.unwrap_or(0),
+            disk_size: size.unwrap_or(0),
+            file_count: 0,
+            dir_count: 0,
+            children_count: 0,
+            is_last_child: false,
+            is_last_entry: false,
+            parent: None,
+            created: None,
+            modified: None,
+            accessed: None,
+            error: None,
+            mount_point: false,
+            link: None,
+            inode: None,
+            path,
+        }
+    }
+
+    #[test]
+    fn tree_lines_have_connectors_and_counts() {
+        let mut tree = Tree::new(&[PathBuf::from("r")]);
+        tree.apply(ScanEvent::Progress(vec![
+            entry("r", None),
+            entry("r/a", None),
+            entry("r/a/x", None),
+            entry("r/a/f", Some(100)),
+            entry("r/b", None),
+            entry("r/b/g", Some(50)),
+            entry("r/b/h", Some(20)),
+        ]));
+        tree.apply(ScanEvent::Finished);
+        let args = PrintArgs {
+            format: Format::Text,
+            sizes: true,
+            counts: true,
+            color: ColorChoice::Never,
+        };
+        let mut out = Vec::new();
+        print_tree(&tree, &args, true, None, &mut out).unwrap();
+
+        // Icons are private-use characters of Nerd Fonts
+        let icon = |c: &char| ('\u{e000}'..='\u{f8ff}').contains(c);
+        let lines: Vec<String> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| line.chars().filter(|c| !icon(c)).collect())
+            .collect();
+        let expected = [
+            " r (170 B, 3 files, 3 dirs)",
+            "│ ├─ a (100 B, 1 file, 1 dir)",
+            "│ │ └─ x (0 B, 0 files, 0 dirs)",
+            "  └─ b (70 B, 2 files, 0 dirs)",
+        ];
+        assert_eq!(lines, expected);
+    }
+}
@@ -0,0 +1,1756 @@
+use crate::stats::{Stats, TimelineSource};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use jwalk::{Parallelism, WalkDirGeneric};
+use std::{
+    cmp,
+    collections::{hash_map::Entry, HashMap},
+    fs, io,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// A single entry in the scanned tree.
+///
+/// Scans report both folders and files; a [`Tree`] keeps the folders as
+/// nodes and folds the files into its statistics and folder totals, and
+/// can keep the files as nodes too.
+#[derive(Clone, Debug)]
+pub struct FileNode {
+    pub path: PathBuf,
+    pub name: String,
+    pub is_dir: bool,
+    pub depth: usize,
+    pub size: u64, // Apparent file size, or the cumulative size of the files below a folder
+    pub disk_size: u64, // Allocated space, for a folder its own plus everything below it
+    pub file_count: usize, // Files anywhere below a folder
+    pub dir_count: usize, // Folders anywhere below a folder
+    pub children_count: usize,
+    pub is_last_child: bool, // Last folder among its siblings
+    pub is_last_entry: bool, // Last among its siblings, files included; for trees that keep files
+    pub parent: Option<usize>, // Index of the parent folder in `Tree::nodes`
+    pub created: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    pub error: Option<io::ErrorKind>, // First error reading this folder or an entry directly in it
+    pub mount_point: bool, // A folder on a different filesystem than its parent
+    pub link: Option<LinkTarget>, // Set for symbolic links
+    pub inode: Option<(u64, u64)>, // Device and inode of an entry other paths may lead to
+}
+
+impl FileNode {
+    /// The apparent size, or the space allocated on disk
+    pub fn usage(&self, apparent: bool) -> u64 {
+        if apparent {
+            self.size
+        } else {
+            self.disk_size
+        }
+    }
+
+    /// The creation time, or the modification time where the filesystem
+    /// records no creation time, with which of the two it is
+    pub fn birth_time(&self) -> Option<(SystemTime, TimelineSource)> {
+        match (self.created, self.modified) {
+            (Some(created), _) => Some((created, TimelineSource::Created)),
+            (None, modified) => modified.map(|modified| (modified, TimelineSource::Modified)),
+        }
+    }
+}
+
+/// A file counted without a node in [`Tree::nodes`], for trees that keep
+/// file entries instead of file nodes; see [`Tree::keep_file_entries`].
+#[derive(Clone, Debug)]
+pub struct FileEntry {
+    pub name: Box<str>,
+    pub depth: usize,
+    pub parent: Option<usize>, // Index of its folder in `Tree::nodes`
+    pub size: u64, // Zero, like the disk size, for another name of a file already counted
+    pub disk_size: u64,
+    pub created: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    pub link: Option<Box<LinkTarget>>,
+    pub inode: Option<(u64, u64)>,
+    position: usize,      // Nodes pushed before it
+    hidden_before: usize, // Hidden entries set aside before it
+}
+
+impl FileEntry {
+    fn new(node: FileNode) -> Self {
+        FileEntry {
+            name: node.name.into(),
+            depth: node.depth,
+            parent: node.parent,
+            size: node.size,
+            disk_size: node.disk_size,
+            created: node.created,
+            modified: node.modified,
+            accessed: node.accessed,
+            link: node.link.map(Box::new),
+            inode: node.inode,
+            position: 0,
+            hidden_before: 0,
+        }
+    }
+
+    /// The file as a node at `path`, ready to be pushed again
+    fn into_node(self, path: PathBuf) -> FileNode {
+        FileNode {
+            path,
+            name: self.name.into(),
+            is_dir: false,
+            depth: self.depth,
+            size: self.size,
+            disk_size: self.disk_size,
+            file_count: 0,
+            dir_count: 0,
+            children_count: 0,
+            is_last_child: false,
+            is_last_entry: false,
+            parent: None,
+            created: self.created,
+            modified: self.modified,
+            accessed: self.accessed,
+            error: None,
+            mount_point: false,
+            link: self.link.map(|link| *link),
+            inode: self.inode,
+        }
+    }
+
+    /// The apparent size, or the space allocated on disk
+    pub fn usage(&self, apparent: bool) -> u64 {
+        if apparent {
+            self.size
+        } else {
+            self.disk_size
+        }
+    }
+}
+
+/// Where a symbolic link points.
+#[derive(Clone, Debug)]
+pub struct LinkTarget {
+    pub path: PathBuf, // As stored in the link, so possibly relative
+    pub broken: bool, // The target does not exist or cannot be reached
+}
+
+impl LinkTarget {
+    /// The target of `path` if it is a symbolic link, without following it.
+    pub fn read(path: &Path) -> Option<Self> {
+        let target = fs::read_link(path).ok()?;
+        Some(LinkTarget {
+            path: target,
+            broken: fs::metadata(path).is_err(),
+        })
+    }
+}
+
+/// A path that could not be read during a scan.
+#[derive(Clone, Debug)]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub kind: io::ErrorKind,
+    pub message: String, // Description of the error, without the path
+}
+
+impl ScanError {
+    fn from_walk(err: &jwalk::Error) -> Option<Self> {
+        Some(ScanError {
+            path: err.path()?.to_path_buf(),
+            kind: err.io_error().map_or(io::ErrorKind::Other, io::Error::kind),
+            message: err
+                .io_error()
+                .map_or_else(|| err.to_string(), io::Error::to_string),
+        })
+    }
+}
+
+// Progress is reported after this many new entries or this much time, whichever comes first
+const BATCH_SIZE: usize = 4096;
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Messages produced while a scan is running.
+#[derive(Clone, Debug)]
+pub enum ScanEvent {
+    /// Entries discovered since the previous event, in depth-first order
+    Progress(Vec<FileNode>),
+    /// Number of entries left out by the scan filters since the previous event
+    Skipped(usize),
+    /// A folder or entry could not be read; it follows the entries before it
+    Error(ScanError),
+    /// A mount point that was not descended into because the scan stays on one filesystem
+    SkippedFilesystem(PathBuf),
+    /// The walk is done
+    Finished,
+}
+
+/// The result of a scan: folders in depth-first order plus the collected statistics.
+///
+/// Each scanned root is a depth-0 node, followed by its subtree.
+#[derive(Clone, Debug)]
+pub struct Tree {
+    pub roots: Vec<PathBuf>,
+    pub nodes: Vec<FileNode>,
+    pub stats: Stats,
+    pub errors: Vec<ScanError>, // In the order they were encountered
+    pub skipped_filesystems: Vec<PathBuf>, // Mount points left unscanned
+    files: Vec<FileEntry>, // Files without a node, in the order they were pushed
+    dir_stack: Vec<usize>, // Node index of the most recent folder at each depth
+    entry_stack: Vec<usize>, // Node index of the most recent node at each depth, files included
+    file_times: Vec<SystemTime>, // File times waiting to be bucketed into the timeline, see `FileNode::birth_time`
+    modified_times: usize, // Of those, modification times standing in for missing creation times
+    seen_inodes: HashMap<(u64, u64), (u64, u64)>, // Entries already counted, with their size and own disk size
+    skip_hidden: bool,
+    keep_files: bool,
+    keep_file_entries: bool,
+    hidden_depth: Option<usize>, // Depth of the hidden entry whose subtree is being dropped
+    hidden: Hidden,
+    finished: bool,
+}
+
+/// What `skip_hidden` dropped, kept aside so [`Tree::set_skip_hidden`] can put
+/// it back. Each item has the number of kept items before it.
+#[derive(Clone, Debug, Default)]
+struct Hidden {
+    entries: Vec<(usize, Aside)>, // The first entry of each subtree has its folder as `parent`
+    errors: Vec<(usize, ScanError)>,
+    filesystems: Vec<(usize, PathBuf)>,
+    subtrees: usize, // Counted in `skipped_entries`
+}
+
+#[derive(Clone, Debug)]
+enum Aside {
+    Folder(FileNode),
+    File(FileEntry),
+}
+
+impl Aside {
+    fn parent_mut(&mut self) -> &mut Option<usize> {
+        match self {
+            Aside::Folder(node) => &mut node.parent,
+            Aside::File(entry) => &mut entry.parent,
+        }
+    }
+}
+
+impl Tree {
+    /// An empty tree, to be filled with [`Tree::apply`] as scan events arrive.
+    pub fn new(roots: &[PathBuf]) -> Self {
+        Tree {
+            roots: roots.to_vec(),
+            nodes: Vec::new(),
+            stats: Stats::new(),
+            errors: Vec::new(),
+            skipped_filesystems: Vec::new(),
+            files: Vec::new(),
+            dir_stack: Vec::new(),
+            entry_stack: Vec::new(),
+            file_times: Vec::new(),
+            modified_times: 0,
+            seen_inodes: HashMap::new(),
+            skip_hidden: false,
+            keep_files: false,
+            keep_file_entries: false,
+            hidden_depth: None,
+            hidden: Hidden::default(),
+            finished: false,
+        }
+    }
+
+    /// Keep files as nodes alongside the folders, in depth-first order.
+    ///
+    /// Files never have children, and folders are marked with
+    /// `is_last_child` as if the files were not there, so the folders alone
+    /// still form a tree.
+    pub fn keep_files(mut self, keep: bool) -> Self {
+        self.keep_files = keep;
+        self
+    }
+
+    /// Keep a [`FileEntry`] for each file that gets no node, which is much
+    /// smaller than a node. Files at the top, without a folder, get a node
+    /// anyway.
+    ///
+    /// Trees that keep either can be rebuilt with other settings, see
+    /// [`Tree::set_keep_files`] and [`Tree::set_skip_hidden`].
+    pub fn keep_file_entries(mut self, keep: bool) -> Self {
+        self.keep_file_entries = keep;
+        self
+    }
+
+    /// Drop pushed entries whose names start with a dot, along with everything
+    /// below them. They count as skipped, and are left out of every total.
+    /// Trees that can be rebuilt keep them aside for [`Tree::set_skip_hidden`].
+    pub fn skip_hidden(mut self, skip: bool) -> Self {
+        self.skip_hidden = skip;
+        self
+    }
+
+    /// Fold a scan event into the tree.
+    pub fn apply(&mut self, event: ScanEvent) {
+        match event {
+            ScanEvent::Progress(entries) => {
+                for entry in entries {
+                    self.push_node(entry);
+                }
+            }
+            ScanEvent::Skipped(count) => self.stats.skipped_entries += count,
+            ScanEvent::Error(error) => self.push_error(error),
+            ScanEvent::SkippedFilesystem(path) => self.push_skipped_filesystem(path),
+            ScanEvent::Finished => self.finish(),
+        }
+    }
+
+    /// Files kept as entries rather than nodes, in the order they were pushed
+    pub fn file_entries(&self) -> &[FileEntry] {
+        &self.files
+    }
+
+    /// The file entries directly in the folder at `folder`, with their indices
+    pub fn files_in(&self, folder: usize) -> impl Iterator<Item = (usize, &FileEntry)> {
+        // Entries come in order of position, and those below a folder sit within its subtree
+        let depth = self.nodes[folder].depth;
+        let end = self.nodes[folder + 1..]
+            .iter()
+            .position(|node| node.depth <= depth)
+            .map_or(self.nodes.len(), |len| folder + 1 + len);
+        let start = self.files.partition_point(|entry| entry.position <= folder);
+        let len = self.files[start..].partition_point(|entry| entry.position <= end);
+        let entries = self.files[start..start + len].iter().zip(start..);
+        entries
+            .filter(move |(entry, _)| entry.parent == Some(folder))
+            .map(|(entry, idx)| (idx, entry))
+    }
+
+    /// Whether files are nodes in [`Tree::nodes`]
+    pub fn keeps_files(&self) -> bool {
+        self.keep_files
+    }
+
+    /// Show or drop hidden entries without rescanning. See [`Tree::set_keep_files`]
+    /// for how the tree is rebuilt.
+    ///
+    /// # Panics
+    ///
+    /// If the tree keeps neither file nodes nor file entries.
+    pub fn set_skip_hidden(&mut self, skip: bool) {
+        if skip != self.skip_hidden {
+            self.rebuild(skip, self.keep_files);
+        }
+    }
+
+    /// Turn files into nodes, or nodes into file entries, without rescanning.
+    ///
+    /// Every entry is pushed again with the new setting, so the totals,
+    /// statistics and errors end up as if it had been used all along. Entries
+    /// keep their order while a scan is running; after [`Tree::sort_by`],
+    /// entries put back go first below their folder and need sorting again.
+    ///
+    /// # Panics
+    ///
+    /// If the tree keeps no file entries and has, or would be left with, no
+    /// file nodes, as its folder totals could not be rebuilt.
+    pub fn set_keep_files(&mut self, keep: bool) {
+        if keep != self.keep_files {
+            self.rebuild(self.skip_hidden, keep);
+        }
+    }
+
+    fn can_rebuild(&self) -> bool {
+        self.keep_files || self.keep_file_entries
+    }
+
+    fn rebuild(&mut self, skip_hidden: bool, keep_files: bool) {
+        assert!(
+            self.keep_file_entries || (self.keep_files && keep_files),
+            "a tree that keeps neither file nodes nor file entries cannot be rebuilt"
+        );
+        let fresh = Tree::new(&self.roots)
+            .skip_hidden(skip_hidden)
+            .keep_files(keep_files)
+            .keep_file_entries(self.keep_file_entries);
+        let old = std::mem::replace(self, fresh);
+        // Only what the scan filters skipped; hidden subtrees are counted again below
+        self.stats.skipped_entries = old.stats.skipped_entries - old.hidden.subtrees;
+
+        // Folders lose everything below them, down to their own disk size
+        let mut own: Vec<u64> = old.nodes.iter().map(|node| node.disk_size).collect();
+        let below = old.nodes.iter().map(|node| (node.parent, node.disk_size));
+        let files = old
+            .files
+            .iter()
+            .map(|entry| (entry.parent, entry.disk_size));
+        for (parent, disk_size) in below.chain(files) {
+            if let Some(parent) = parent {
+                own[parent] = own[parent].saturating_sub(disk_size);
+            }
+        }
+        // Duplicates were emptied; which one counts may change
+        let counted = |inode: Option<(u64, u64)>| inode.and_then(|id| old.seen_inodes.get(&id));
+        let mut nodes = old
+            .nodes
+            .into_iter()
+            .zip(own)
+            .map(|(mut node, own)| {
+                node.disk_size = own;
+                if node.is_dir {
+                    node.size = 0;
+                }
+                if let Some(&(size, disk_size)) = counted(node.inode) {
+                    node.size = size;
+                    node.disk_size = disk_size;
+                }
+                node.file_count = 0;
+                node.dir_count = 0;
+                node.children_count = 0;
+                node.is_last_child = false;
+                node.is_last_entry = false;
+                node.parent = None;
+                node.error = None;
+                node
+            })
+            .peekable();
+        let mut files = old.files.into_iter().peekable();
+        let mut hidden = old.hidden.entries.into_iter().peekable();
+
+        // Push everything again in the order it was first pushed
+        let (mut pushed_nodes, mut pushed_hidden) = (0, 0);
+        let mut folders: Vec<PathBuf> = Vec::new(); // Path of the most recent folder at each depth
+        loop {
+            let file_next = files.peek().is_some_and(|entry| {
+                entry.position <= pushed_nodes && entry.hidden_before <= pushed_hidden
+            });
+            let hidden_next = hidden.peek().is_some_and(|&(at, _)| at <= pushed_nodes)
+                && files
+                    .peek()
+                    .is_none_or(|entry| pushed_hidden < entry.hidden_before);
+            let entry = if file_next {
+                files.next().map(Aside::File)
+            } else if hidden_next {
+                pushed_hidden += 1;
+                hidden.next().map(|(_, entry)| entry)
+            } else if let Some(node) = nodes.next() {
+                pushed_nodes += 1;
+                Some(Aside::Folder(node))
+            } else {
+                files
+                    .next()
+                    .map(Aside::File)
+                    .or_else(|| hidden.next().map(|(_, entry)| entry))
+            };
+            let mut node = match entry {
+                None => break,
+                Some(Aside::Folder(node)) => node,
+                Some(Aside::File(mut entry)) => {
+                    if let Some(&(size, disk_size)) = counted(entry.inode) {
+                        entry.size = size;
+                        entry.disk_size = disk_size;
+                    }
+                    let path = match entry.depth.checked_sub(1).and_then(|up| folders.get(up)) {
+                        Some(folder) => folder.join(&*entry.name),
+                        None => PathBuf::from(&*entry.name),
+                    };
+                    entry.into_node(path)
+                }
+            };
+            if node.is_dir {
+                folders.truncate(node.depth);
+                folders.push(node.path.clone());
+            }
+            node.parent = None;
+            self.push_node(node);
+        }
+
+        // Errors are matched to their folders by path, as the folders are all pushed
+        let folders: HashMap<&Path, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_dir)
+            .map(|(idx, node)| (node.path.as_path(), idx))
+            .collect();
+        let mut marks = Vec::new();
+        for error in merge(old.errors, old.hidden.errors) {
+            if self.skip_hidden && self.is_hidden(&error.path) {
+                self.hidden.errors.push((self.errors.len(), error));
+                continue;
+            }
+            if let Some(&idx) = error.path.ancestors().find_map(|dir| folders.get(dir)) {
+                marks.push((idx, error.kind));
+            }
+            self.errors.push(error);
+        }
+        for (idx, kind) in marks {
+            self.nodes[idx].error.get_or_insert(kind);
+        }
+        for path in merge(old.skipped_filesystems, old.hidden.filesystems) {
+            self.push_skipped_filesystem(path);
+        }
+        if old.finished {
+            self.finish();
+        }
+    }
+
+    fn push_skipped_filesystem(&mut self, path: PathBuf) {
+        if self.skip_hidden && self.is_hidden(&path) {
+            let position = self.skipped_filesystems.len();
+            self.hidden.filesystems.push((position, path));
+        } else {
+            self.skipped_filesystems.push(path);
+        }
+    }
+
+    /// Add an entry that follows the existing ones in depth-first order.
+    ///
+    /// Folders are appended to `nodes`, starting out as the last child of
+    /// their parent; the previous sibling, if any, stops being the last child.
+    /// Files update the statistics and the totals of their ancestors, and are
+    /// only appended if the tree keeps them, or kept as entries.
+    /// An entry with the same device and inode as an earlier one, such as a
+    /// file with several hard links, or a file or folder reached again
+    /// through a followed symbolic link, adds nothing to the sizes. Hidden
+    /// entries are dropped if the tree skips them.
+    pub fn push_node(&mut self, mut node: FileNode) {
+        let depth = node.depth;
+        // The first `depth` entries of the stack are this entry's ancestors
+        let ancestors = depth.min(self.dir_stack.len());
+        let parent = ancestors.checked_sub(1).map(|i| self.dir_stack[i]);
+        if self.skip_hidden {
+            match self.hidden_depth {
+                Some(hidden) if depth > hidden => {
+                    self.set_aside(node);
+                    return;
+                }
+                _ => self.hidden_depth = None,
+            }
+            if depth > 0 && node.name.starts_with('.') {
+                self.hidden_depth = Some(depth);
+                self.stats.skipped_entries += 1;
+                self.hidden.subtrees += 1;
+                node.parent = parent;
+                self.set_aside(node);
+                return;
+            }
+        }
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+        if let Some(inode) = node.inode {
+            match self.seen_inodes.entry(inode) {
+                Entry::Occupied(_) => {
+                    node.size = 0;
+                    node.disk_size = 0;
+                    if !node.is_dir {
+                        self.stats.hard_links += 1;
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((node.size, node.disk_size));
+                }
+            }
+        }
+
+        node.parent = parent;
+        if let Some(parent) = node.parent {
+            self.nodes[parent].children_count += 1;
+        }
+        for &ancestor in &self.dir_stack[..ancestors] {
+            let ancestor = &mut self.nodes[ancestor];
+            ancestor.disk_size += node.disk_size;
+            if node.is_dir {
+                ancestor.dir_count += 1;
+            } else {
+                ancestor.file_count += 1;
+                ancestor.size += node.size;
+            }
+        }
+
+        if node.link.as_ref().is_some_and(|link| link.broken) {
+            self.stats.broken_links += 1;
+        }
+        self.stats.total_disk_size += node.disk_size;
+        if !node.is_dir {
+            self.stats.total_files += 1;
+            self.stats.total_size += node.size;
+            if let Some((time, source)) = node.birth_time() {
+                self.file_times.push(time);
+                if source == TimelineSource::Modified {
+                    self.modified_times += 1;
+                }
+            }
+            if self.keep_files || (self.keep_file_entries && node.parent.is_none()) {
+                self.push_entry(node);
+            } else if self.keep_file_entries {
+                let mut entry = FileEntry::new(node);
+                entry.position = self.nodes.len();
+                entry.hidden_before = self.hidden.entries.len();
+                self.files.push(entry);
+            }
+            return;
+        }
+
+        self.stats.total_dirs += 1;
+        if let Some(&prev) = self.dir_stack.get(depth) {
+            self.nodes[prev].is_last_child = false;
+        }
+        // Anything at this depth or deeper belonged to the previous sibling's subtree
+        self.dir_stack.truncate(depth);
+        self.dir_stack.push(self.nodes.len());
+
+        node.is_last_child = true;
+        self.push_entry(node);
+    }
+
+    /// Keep a dropped hidden entry, if the tree can bring it back
+    fn set_aside(&mut self, node: FileNode) {
+        if !self.can_rebuild() {
+            return;
+        }
+        let entry = match node.is_dir {
+            true => Aside::Folder(node),
+            false => Aside::File(FileEntry::new(node)),
+        };
+        self.hidden.entries.push((self.nodes.len(), entry));
+    }
+
+    /// Append a node, making it the last entry among its siblings
+    fn push_entry(&mut self, mut node: FileNode) {
+        let depth = node.depth;
+        // Deeper entries belonged to the previous sibling's subtree
+        self.entry_stack.truncate(depth + 1);
+        if let Some(&prev) = self.entry_stack.get(depth) {
+            self.nodes[prev].is_last_entry = false;
+        }
+        self.entry_stack.truncate(depth);
+        self.entry_stack.push(self.nodes.len());
+
+        node.is_last_entry = true;
+        self.nodes.push(node);
+    }
+
+    /// Record an error, marking the folder it belongs to: the folder itself if it
+    /// could not be read, otherwise the folder holding the failing entry.
+    pub fn push_error(&mut self, error: ScanError) {
+        if self.skip_hidden && self.is_hidden(&error.path) {
+            self.hidden.errors.push((self.errors.len(), error));
+            return;
+        }
+        // Entries arrive depth-first, so the folder is on the stack of open folders
+        let folder = self
+            .dir_stack
+            .iter()
+            .rev()
+            .find(|&&idx| error.path.starts_with(&self.nodes[idx].path));
+        if let Some(&idx) = folder {
+            self.nodes[idx].error.get_or_insert(error.kind);
+        }
+        self.errors.push(error);
+    }
+
+    /// Whether `path` is, or is inside, an entry whose name starts with a dot
+    fn is_hidden(&self, path: &Path) -> bool {
+        let relative = self
+            .roots
+            .iter()
+            .find_map(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path);
+        relative
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+    }
+
+    /// Finalize the statistics once every entry has been pushed.
+    pub fn finish(&mut self) {
+        self.finished = true;
+        // Build histogram from file times for a small bar chart
+        self.stats.build_timeline(&mut self.file_times);
+        self.stats.timeline_source = match self.modified_times {
+            0 => TimelineSource::Created,
+            n if n == self.file_times.len() => TimelineSource::Modified,
+            _ => TimelineSource::Mixed,
+        };
+    }
+
+    /// Reorder the children of every folder with `compare`, keeping the
+    /// depth-first layout; roots keep their order.
+    ///
+    /// Nodes move, so `parent`, `is_last_child` and `is_last_entry` are
+    /// recomputed. Meant for finished scans: entries pushed afterwards would
+    /// no longer follow their folders.
+    pub fn sort_by(&mut self, mut compare: impl FnMut(&FileNode, &FileNode) -> cmp::Ordering) {
+        let mut children = vec![Vec::new(); self.nodes.len()];
+        let mut roots = Vec::new();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            match node.parent {
+                Some(parent) => children[parent].push(idx),
+                None => roots.push(idx),
+            }
+        }
+        for siblings in &mut children {
+            siblings.sort_by(|&a, &b| compare(&self.nodes[a], &self.nodes[b]));
+        }
+
+        // Walk the sorted children depth-first to get the new order
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut stack: Vec<usize> = roots.into_iter().rev().collect();
+        while let Some(idx) = stack.pop() {
+            order.push(idx);
+            stack.extend(children[idx].iter().rev());
+        }
+        let mut new_index = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_index[old] = new;
+        }
+
+        let mut old_nodes: Vec<Option<FileNode>> = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .map(Some)
+            .collect();
+        self.nodes = order
+            .iter()
+            .filter_map(|&old| old_nodes[old].take())
+            .map(|mut node| {
+                node.parent = node.parent.map(|parent| new_index[parent]);
+                node
+            })
+            .collect();
+        for siblings in &children {
+            for &old in siblings {
+                let node = &mut self.nodes[new_index[old]];
+                node.is_last_child = false;
+                node.is_last_entry = false;
+            }
+            if let Some(&last) = siblings.last() {
+                self.nodes[new_index[last]].is_last_entry = true;
+            }
+            let is_dir = |old: &&usize| self.nodes[new_index[**old]].is_dir;
+            if let Some(&last) = siblings.iter().rev().find(is_dir) {
+                self.nodes[new_index[last]].is_last_child = true;
+            }
+        }
+
+        // Hidden subtrees that were dropped move with their folder, right below it
+        let mut position = 0;
+        for (at, entry) in &mut self.hidden.entries {
+            if let Some(parent) = entry.parent_mut() {
+                *parent = new_index[*parent];
+                position = *parent + 1;
+            }
+            *at = position;
+        }
+        self.hidden.entries.sort_by_key(|&(at, _)| at);
+        // So do files, ahead of the hidden entries there
+        for entry in &mut self.files {
+            if let Some(parent) = &mut entry.parent {
+                *parent = new_index[*parent];
+                entry.position = *parent + 1;
+            }
+        }
+        self.files.sort_by_key(|entry| entry.position);
+        for entry in &mut self.files {
+            let position = entry.position;
+            entry.hidden_before = self
+                .hidden
+                .entries
+                .partition_point(|&(at, _)| at < position);
+        }
+    }
+}
+
+// Per-folder state handed down to subfolders: the ignore files in effect and the folders above.
+// Per-entry state filled in by the walker threads: see `EntryState`.
+type WalkState = ((IgnoreStack, Ancestors), EntryState);
+
+#[derive(Debug, Default)]
+struct EntryState {
+    metadata: Option<Result<fs::Metadata, (io::ErrorKind, String)>>, // Or why it is missing
+    link: Option<LinkTarget>,
+    looped: Option<PathBuf>, // The folder a link leads back to, instead of being followed
+}
+
+/// Identities of the folders being read, innermost first; only tracked when following links
+#[derive(Clone, Debug, Default)]
+struct Ancestors(Option<Arc<Ancestor>>);
+
+#[derive(Debug)]
+struct Ancestor {
+    id: (u64, u64), // Device and inode
+    path: PathBuf,
+    parent: Ancestors,
+}
+
+impl Ancestors {
+    fn enter(&mut self, dir: &Path) {
+        if let Some(id) = fs::metadata(dir).ok().as_ref().and_then(file_id) {
+            let parent = std::mem::take(self);
+            *self = Ancestors(Some(Arc::new(Ancestor {
+                id,
+                path: dir.to_path_buf(),
+                parent,
+            })));
+        }
+    }
+
+    /// The folder with identity `id`, if it is one of the ancestors
+    fn find(&self, id: (u64, u64)) -> Option<&Path> {
+        let mut ancestor = self.0.as_deref();
+        while let Some(current) = ancestor {
+            if current.id == id {
+                return Some(&current.path);
+            }
+            ancestor = current.parent.0.as_deref();
+        }
+        None
+    }
+}
+
+/// The ignore files that apply to a folder, innermost first
+#[derive(Clone, Debug, Default)]
+struct IgnoreStack(Option<Arc<IgnoreLayer>>);
+
+#[derive(Debug)]
+struct IgnoreLayer {
+    matcher: Gitignore,
+    parent: IgnoreStack,
+}
+
+impl IgnoreStack {
+    /// Add the `.gitignore` and `.ignore` files of `dir`, if it has any
+    fn enter(&mut self, dir: &Path) {
+        let mut builder = GitignoreBuilder::new(dir);
+        // Later files take precedence, so `.ignore` overrides `.gitignore`
+        for name in [".gitignore", ".ignore"] {
+            let file = dir.join(name);
+            if file.is_file() {
+                let _ = builder.add(file);
+            }
+        }
+        if let Ok(matcher) = builder.build() {
+            if !matcher.is_empty() {
+                let parent = std::mem::take(self);
+                *self = IgnoreStack(Some(Arc::new(IgnoreLayer { matcher, parent })));
+            }
+        }
+    }
+
+    /// Whether the innermost ignore file with an opinion about `path` ignores it
+    fn decide(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let mut layer = self.0.as_deref();
+        while let Some(current) = layer {
+            let matched = current.matcher.matched(path, is_dir);
+            if !matched.is_none() {
+                return Some(matched.is_ignore());
+            }
+            layer = current.parent.0.as_deref();
+        }
+        None
+    }
+}
+
+/// Decides which entries a walk leaves out; shared by the walker threads of one root
+#[derive(Debug)]
+struct Filter {
+    root: PathBuf,
+    skip_hidden: bool,
+    ignore_files: bool,
+    global_ignore: Arc<Gitignore>,
+    exclude: Arc<GlobSet>,
+    include: Arc<GlobSet>,
+    skipped: Arc<AtomicUsize>,
+    root_device: Option<u64>, // Set when the walk stays on the root's filesystem
+    follow_links: bool,
+}
+
+impl Filter {
+    fn keep(&self, ignores: &IgnoreStack, path: &Path, name: &str, is_dir: bool) -> bool {
+        if self.skip_hidden && name.starts_with('.') {
+            return false;
+        }
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        if self.exclude.is_match(name) || self.exclude.is_match(relative) {
+            return false;
+        }
+        if !is_dir
+            && !self.include.is_empty()
+            && !self.include.is_match(name)
+            && !self.include.is_match(relative)
+        {
+            return false;
+        }
+        if self.ignore_files {
+            // Git's own metadata is never interesting when honoring its ignore rules
+            if is_dir && name == ".git" {
+                return false;
+            }
+            let ignored = ignores
+                .decide(path, is_dir)
+                .unwrap_or_else(|| self.global_ignore.matched(path, is_dir).is_ignore());
+            if ignored {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Walks one or more directories and builds a [`Tree`] without any terminal interaction.
+#[derive(Clone, Debug)]
+pub struct Scanner {
+    roots: Vec<PathBuf>,
+    follow_links: bool,
+    threads: usize,
+    max_depth: Option<usize>,
+    skip_hidden: bool,
+    ignore_files: bool,
+    one_file_system: bool,
+    exclude: Vec<String>, // Glob patterns, compiled when the walk starts
+    include: Vec<String>,
+}
+
+impl Scanner {
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Scanner {
+            roots: vec![root.as_ref().to_path_buf()],
+            follow_links: false,
+            threads: 0,
+            max_depth: None,
+            skip_hidden: false,
+            ignore_files: false,
+            one_file_system: false,
+            exclude: Vec::new(),
+            include: Vec::new(),
+        }
+    }
+
+    /// Scan another directory after the existing ones; it becomes a separate depth-0 node.
+    pub fn add_root(mut self, root: impl AsRef<Path>) -> Self {
+        self.roots.push(root.as_ref().to_path_buf());
+        self
+    }
+
+    /// Descend into symbolic links to folders and count linked files at their
+    /// target's size. Links that lead back to a folder being walked are
+    /// reported as errors and not followed.
+    ///
+    /// Without this, links are counted as entries of their own. Loops are
+    /// only detected on Unix.
+    pub fn follow_links(mut self, follow: bool) -> Self {
+        self.follow_links = follow;
+        self
+    }
+
+    /// Number of walker threads. `0` uses one per CPU, `1` walks on the calling thread.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Do not descend more than `depth` levels below each root.
+    pub fn max_depth(mut self, depth: Option<usize>) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Leave out files and folders whose names start with a dot.
+    pub fn skip_hidden(mut self, skip: bool) -> Self {
+        self.skip_hidden = skip;
+        self
+    }
+
+    /// Leave out entries matched by `.gitignore` and `.ignore` files found while
+    /// walking, using gitignore rules, and by the global git excludes file.
+    /// `.git` folders are left out too.
+    pub fn ignore_files(mut self, enabled: bool) -> Self {
+        self.ignore_files = enabled;
+        self
+    }
+
+    /// Do not descend into folders on a different filesystem than their root.
+    ///
+    /// The mount points themselves are still listed, and reported with
+    /// [`ScanEvent::SkippedFilesystem`]. Only supported on Unix.
+    pub fn one_file_system(mut self, enabled: bool) -> Self {
+        self.one_file_system = enabled;
+        self
+    }
+
+    /// Leave out entries whose name, or path relative to their root, matches the glob.
+    ///
+    /// Excluded folders are not descended into. Invalid patterns make the scan fail.
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Only keep files whose name, or path relative to their root, matches one of
+    /// the include globs. Folders are always walked.
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Scan the whole tree. Only directories become nodes; files are counted in the stats.
+    pub fn scan(&self) -> io::Result<Tree> {
+        let mut tree = Tree::new(&self.roots);
+        self.walk(|event| {
+            tree.apply(event);
+            ControlFlow::Continue(())
+        })?;
+        Ok(tree)
+    }
+
+    /// Run the scan on a worker thread, streaming [`ScanEvent`]s over a channel.
+    ///
+    /// Dropping the receiver stops the worker at its next progress report.
+    pub fn spawn(&self) -> io::Result<Receiver<ScanEvent>> {
+        self.check_root()?;
+        let (tx, rx) = mpsc::channel();
+        let scanner = self.clone();
+        thread::spawn(move || {
+            let _ = scanner.walk(|event| match tx.send(event) {
+                Ok(()) => ControlFlow::Continue(()),
+                Err(_) => ControlFlow::Break(()),
+            });
+        });
+        Ok(rx)
+    }
+
+    /// Walk the roots one after another, reporting batches of entries to `emit`
+    /// in depth-first order.
+    ///
+    /// Directories are read in parallel; returning `ControlFlow::Break` from
+    /// `emit` stops the walk early.
+    pub fn walk(&self, mut emit: impl FnMut(ScanEvent) -> ControlFlow<()>) -> io::Result<()> {
+        self.check_root()?;
+        let exclude = Arc::new(glob_set(&self.exclude, "exclude")?);
+        let include = Arc::new(glob_set(&self.include, "include")?);
+        let global_ignore = Arc::new(if self.ignore_files {
+            Gitignore::global().0
+        } else {
+            Gitignore::empty()
+        });
+        let skipped = Arc::new(AtomicUsize::new(0));
+
+        let mut batch = Vec::new();
+        let mut last_emit = Instant::now();
+        let mut devices: Vec<Option<u64>> = Vec::new(); // Device of the most recent folder at each depth
+
+        for root in &self.roots {
+            let parallelism = match self.threads {
+                0 => Parallelism::RayonDefaultPool {
+                    busy_timeout: Duration::from_secs(1),
+                },
+                1 => Parallelism::Serial,
+                n => Parallelism::RayonNewPool(n),
+            };
+
+            let filter = Filter {
+                root: root.clone(),
+                skip_hidden: self.skip_hidden,
+                ignore_files: self.ignore_files,
+                global_ignore: Arc::clone(&global_ignore),
+                exclude: Arc::clone(&exclude),
+                include: Arc::clone(&include),
+                skipped: Arc::clone(&skipped),
+                root_device: match self.one_file_system {
+                    true => fs::metadata(root).ok().as_ref().and_then(device),
+                    false => None,
+                },
+                follow_links: self.follow_links,
+            };
+            let root_device = filter.root_device;
+            let mut walker = WalkDirGeneric::<WalkState>::new(root)
+                .follow_links(false) // Links are followed below, with inode-based loop detection
+                .skip_hidden(false)
+                .sort(true)
+                .parallelism(parallelism)
+                .process_read_dir(move |depth, dir, (ignores, ancestors), children| {
+                    // `depth` is None for the root itself, which is never filtered
+                    if depth.is_some() {
+                        if filter.ignore_files {
+                            ignores.enter(dir);
+                        }
+                        if filter.follow_links {
+                            ancestors.enter(dir);
+                        }
+                        let before = children.len();
+                        children.retain(|child| match child {
+                            Ok(child) => filter.keep(
+                                ignores,
+                                &child.path(),
+                                &child.file_name.to_string_lossy(),
+                                child.file_type.is_dir(),
+                            ),
+                            Err(_) => true,
+                        });
+                        let skipped = before - children.len();
+                        filter.skipped.fetch_add(skipped, Ordering::Relaxed);
+                    }
+                    // Stat entries on the walker threads so the consumer never blocks on IO
+                    for child in children.iter_mut().flatten() {
+                        let path = child.path();
+                        let mut state = EntryState::default();
+                        // The root is always read, even through a link
+                        let mut metadata = match depth {
+                            Some(_) => fs::symlink_metadata(&path),
+                            None => fs::metadata(&path),
+                        };
+                        if depth.is_some() && child.file_type.is_symlink() {
+                            state.link = LinkTarget::read(&path);
+                            let target = fs::metadata(&path).ok().filter(|_| filter.follow_links);
+                            if let Some(target) = target {
+                                let id = file_id(&target).filter(|_| target.is_dir());
+                                match id.and_then(|id| ancestors.find(id)) {
+                                    Some(ancestor) => state.looped = Some(ancestor.to_path_buf()),
+                                    None => {
+                                        if target.is_dir() {
+                                            child.read_children_path = Some(path.as_path().into());
+                                        }
+                                        metadata = Ok(target);
+                                    }
+                                }
+                            }
+                        }
+                        // Leave other filesystems unread; the mount point itself stays listed
+                        if let (Some(root), Ok(metadata)) = (filter.root_device, &metadata) {
+                            if device(metadata).is_some_and(|dev| dev != root) {
+                                child.read_children_path = None;
+                            }
+                        }
+                        state.metadata = Some(metadata.map_err(|e| (e.kind(), e.to_string())));
+                        child.client_state = state;
+                    }
+                });
+            if let Some(depth) = self.max_depth {
+                walker = walker.max_depth(depth);
+            }
+
+            for entry in walker {
+                let mut entry = match entry {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        // Folders that cannot be listed arrive as `read_children_error` below
+                        if let Some(error) = ScanError::from_walk(&err) {
+                            let event = ScanEvent::Error(error);
+                            if emit_after_batch(&mut emit, &mut batch, &skipped, event).is_break() {
+                                return Ok(());
+                            }
+                        }
+                        continue;
+                    }
+                };
+                let path = entry.path();
+                // Events about this entry, reported once it is in the batch
+                let mut events = Vec::new();
+                let state = std::mem::take(&mut entry.client_state);
+                if let Some(ancestor) = state.looped {
+                    events.push(ScanEvent::Error(ScanError {
+                        path: path.clone(),
+                        kind: io::ErrorKind::Other,
+                        message: format!(
+                            "file system loop: links back to '{}'",
+                            ancestor.display()
+                        ),
+                    }));
+                }
+                let metadata = match state.metadata {
+                    Some(Ok(metadata)) => Some(metadata),
+                    Some(Err((kind, message))) => {
+                        events.push(ScanEvent::Error(ScanError {
+                            path: path.clone(),
+                            kind,
+                            message,
+                        }));
+                        None
+                    }
+                    None => None,
+                };
+                if let Some(err) = entry.read_children_error.take() {
+                    events.extend(ScanError::from_walk(&err).map(ScanEvent::Error));
+                }
+                // Links only count as folders when followed; then the metadata is the target's
+                let is_dir = match &metadata {
+                    Some(metadata) => metadata.is_dir(),
+                    None => entry.file_type.is_dir(),
+                };
+                let size = match &metadata {
+                    Some(metadata) if !is_dir => metadata.len(),
+                    _ => 0,
+                };
+                let disk_size = metadata.as_ref().map_or(0, allocated);
+                let created = metadata.as_ref().and_then(|m| m.created().ok());
+                let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+                let accessed = metadata.as_ref().and_then(|m| m.accessed().ok());
+                // Files with other names are only counted once; when following links,
+                // so is every file and folder a link may also lead to, like `du -L`
+                let inode = match &metadata {
+                    Some(metadata) if self.follow_links || (!is_dir && links(metadata) > 1) => {
+                        file_id(metadata)
+                    }
+                    _ => None,
+                };
+
+                // A folder whose device differs from its parent's
+                let dev = metadata.as_ref().and_then(device);
+                let mut mount_point = false;
+                if is_dir {
+                    devices.truncate(entry.depth);
+                    mount_point = entry.depth > 0
+                        && dev.is_some()
+                        && devices.get(entry.depth - 1).is_some_and(|&up| up != dev);
+                    devices.push(dev);
+                }
+                if mount_point && root_device.is_some_and(|root| dev != Some(root)) {
+                    events.push(ScanEvent::SkippedFilesystem(path.clone()));
+                }
+
+                batch.push(FileNode {
+                    name: path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    path,
+                    is_dir,
+                    depth: entry.depth,
+                    size,
+                    disk_size,
+                    // Totals, links and child counts are filled in by Tree::push_node
+                    file_count: 0,
+                    dir_count: 0,
+                    children_count: 0,
+                    is_last_child: false,
+                    is_last_entry: false,
+                    parent: None,
+                    created,
+                    modified,
+                    accessed,
+                    error: None,
+                    mount_point,
+                    link: state.link,
+                    inode,
+                });
+                for event in events {
+                    if emit_after_batch(&mut emit, &mut batch, &skipped, event).is_break() {
+                        return Ok(());
+                    }
+                }
+
+                if batch.len() >= BATCH_SIZE || last_emit.elapsed() >= BATCH_INTERVAL {
+                    let entries = std::mem::take(&mut batch);
+                    if emit_progress(&mut emit, entries, &skipped).is_break() {
+                        return Ok(());
+                    }
+                    last_emit = Instant::now();
+                }
+            }
+        }
+
+        if emit_progress(&mut emit, batch, &skipped).is_break() {
+            return Ok(());
+        }
+        let _ = emit(ScanEvent::Finished);
+        Ok(())
+    }
+
+    fn check_root(&self) -> io::Result<()> {
+        for root in &self.roots {
+            if !root.is_dir() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("'{}' is not a directory", root.display()),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Report a batch of entries, preceded by the number of entries skipped since the last batch
+fn emit_progress(
+    emit: &mut impl FnMut(ScanEvent) -> ControlFlow<()>,
+    entries: Vec<FileNode>,
+    skipped: &AtomicUsize,
+) -> ControlFlow<()> {
+    let skipped = skipped.swap(0, Ordering::Relaxed);
+    if skipped > 0 {
+        emit(ScanEvent::Skipped(skipped))?;
+    }
+    emit(ScanEvent::Progress(entries))
+}
+
+/// Report an event right after the entries collected so far, so it follows the entry it is about
+fn emit_after_batch(
+    emit: &mut impl FnMut(ScanEvent) -> ControlFlow<()>,
+    batch: &mut Vec<FileNode>,
+    skipped: &AtomicUsize,
+    event: ScanEvent,
+) -> ControlFlow<()> {
+    emit_progress(emit, std::mem::take(batch), skipped)?;
+    emit(event)
+}
+
+/// The ID of the device a file is stored on, where the platform has one
+#[cfg(unix)]
+fn device(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_metadata: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// The space allocated for a file, which can be less than its length for sparse
+/// files and more for small ones; where unknown, the length
+#[cfg(unix)]
+fn allocated(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.blocks() * 512 // `st_blocks` is in 512-byte units whatever the block size
+}
+
+#[cfg(not(unix))]
+fn allocated(metadata: &fs::Metadata) -> u64 {
+    metadata.len()
+}
+
+/// Number of hard links to a file
+#[cfg(unix)]
+fn links(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink()
+}
+
+#[cfg(not(unix))]
+fn links(_metadata: &fs::Metadata) -> u64 {
+    1
+}
+
+/// The device and inode of a file, which identify it whatever path leads to it
+#[cfg(unix)]
+fn file_id(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Put dropped items back before the kept item at their position; both lists are in order
+fn merge<T>(kept: Vec<T>, dropped: Vec<(usize, T)>) -> Vec<T> {
+    let mut merged = Vec::with_capacity(kept.len() + dropped.len());
+    let mut dropped = dropped.into_iter().peekable();
+    for (idx, item) in kept.into_iter().enumerate() {
+        while let Some((_, back)) = dropped.next_if(|&(at, _)| at <= idx) {
+            merged.push(back);
+        }
+        merged.push(item);
+    }
+    merged.extend(dropped.map(|(_, back)| back));
+    merged
+}
+
+fn glob_set(patterns: &[String], option: &str) -> io::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid {} pattern '{}': {}", option, pattern, e.kind()),
+            )
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn globs(patterns: &[&str]) -> Arc<GlobSet> {
+        let patterns: Vec<String> = patterns.iter().map(|glob| glob.to_string()).collect();
+        Arc::new(glob_set(&patterns, "--exclude").unwrap())
+    }
+
+    fn filter(exclude: &[&str], include: &[&str]) -> Filter {
+        Filter {
+            root: PathBuf::from("/root"),
+            skip_hidden: false,
+            ignore_files: false,
+            global_ignore: Arc::new(Gitignore::empty()),
+            exclude: globs(exclude),
+            include: globs(include),
+            skipped: Arc::default(),
+            root_device: None,
+            follow_links: false,
+        }
+    }
+
+    fn keeps(filter: &Filter, relative: &str, is_dir: bool) -> bool {
+        let path = filter.root.join(relative);
+        let name = path.file_name().unwrap().to_str().unwrap().to_string();
+        filter.keep(&IgnoreStack::default(), &path, &name, is_dir)
+    }
+
+    #[test]
+    fn exclude_matches_names_and_relative_paths() {
+        let filter = filter(&["*.log", "build/out"], &[]);
+        assert!(!keeps(&filter, "app.log", false));
+        assert!(!keeps(&filter, "deep/down/app.log", false));
+        assert!(!keeps(&filter, "build/out", true));
+        assert!(keeps(&filter, "other/out", true));
+        assert!(keeps(&filter, "build/app.txt", false));
+    }
+
+    #[test]
+    fn include_only_limits_files() {
+        let filter = filter(&[], &["*.rs", "docs/*.md"]);
+        assert!(keeps(&filter, "src/main.rs", false));
+        assert!(keeps(&filter, "docs/guide.md", false));
+        assert!(!keeps(&filter, "README.md", false));
+        assert!(!keeps(&filter, "Cargo.toml", false));
+        // Folders stay so files below them can match
+        assert!(keeps(&filter, "target", true));
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let filter = filter(&["generated*"], &["*.rs"]);
+        assert!(!keeps(&filter, "src/generated.rs", false));
+        assert!(!keeps(&filter, "generated", true));
+        assert!(keeps(&filter, "src/lib.rs", false));
+    }
+
+    #[test]
+    fn without_patterns_everything_stays() {
+        let filter = filter(&[], &[]);
+        assert!(keeps(&filter, "anything.bin", false));
+        assert!(keeps(&filter, ".hidden", false));
+        assert!(keeps(&filter, ".git", true));
+    }
+
+    #[test]
+    fn hidden_entries_and_git_metadata_can_be_skipped() {
+        let mut filter = filter(&[], &[]);
+        filter.skip_hidden = true;
+        assert!(!keeps(&filter, ".cache", true));
+        assert!(keeps(&filter, "visible", false));
+        filter.skip_hidden = false;
+        filter.ignore_files = true;
+        assert!(!keeps(&filter, ".git", true));
+        assert!(keeps(&filter, ".gitignore", false));
+    }
+
+    /// A folder (`size` none) or file below the root `r`, as a scan reports it
+    fn entry(path: &str, size: Option<u64>) -> FileNode {
+        let path = PathBuf::from(path);
+        FileNode {
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            is_dir: size.is_none(),
+            depth: path.components().count() - 1,
+            size: size.unwrap_or(0),
+            disk_size: size.map_or(4, |size| size * 2),
+            file_count: 0,
+            dir_count: 0,
+            children_count: 0,
+            is_last_child: false,
+            is_last_entry: false,
+            parent: None,
+            created: None,
+            modified: None,
+            accessed: None,
+            error: None,
+            mount_point: false,
+            link: None,
+            inode: None,
+            path,
+        }
+    }
+
+    fn sample() -> Vec<FileNode> {
+        vec![
+            entry("r", None),
+            entry("r/b", None),
+            entry("r/b/.cache", None),
+            entry("r/b/.cache/blob", Some(50)),
+            entry("r/b/main", Some(100)),
+            entry("r/b/.env", Some(7)),
+            entry("r/a", None),
+            entry("r/a/notes", Some(20)),
+        ]
+    }
+
+    fn build(entries: Vec<FileNode>, skip_hidden: bool, keep_files: bool) -> Tree {
+        let mut tree = Tree::new(&[PathBuf::from("r")])
+            .skip_hidden(skip_hidden)
+            .keep_files(keep_files)
+            .keep_file_entries(!keep_files);
+        tree.apply(ScanEvent::Progress(entries));
+        tree.apply(ScanEvent::Finished);
+        tree
+    }
+
+    /// Path, sizes and counts of every node, with the overall statistics
+    fn totals(tree: &Tree) -> (Vec<(&Path, [u64; 4])>, [u64; 5]) {
+        let nodes = tree.nodes.iter().map(|node| {
+            let counts = [node.file_count as u64, node.dir_count as u64];
+            let figures = [node.size, node.disk_size, counts[0], counts[1]];
+            (node.path.as_path(), figures)
+        });
+        let stats = &tree.stats;
+        let overall = [
+            stats.total_files as u64,
+            stats.total_dirs as u64,
+            stats.total_size,
+            stats.total_disk_size,
+            stats.skipped_entries as u64,
+        ];
+        (nodes.collect(), overall)
+    }
+
+    /// Every node sits in its parent's subtree, and files kept as entries
+    /// point at their folder
+    fn assert_consistent(tree: &Tree) {
+        for (idx, node) in tree.nodes.iter().enumerate() {
+            let Some(parent) = node.parent else {
+                continue;
+            };
+            assert_eq!(Some(tree.nodes[parent].path.as_path()), node.path.parent());
+            let between = &tree.nodes[parent + 1..idx];
+            assert!(between.iter().all(|other| other.depth > node.depth - 1));
+        }
+        for entry in tree.file_entries() {
+            let folder = &tree.nodes[entry.parent.unwrap()];
+            assert_eq!(folder.depth + 1, entry.depth);
+        }
+    }
+
+    fn by_name(tree: &mut Tree) {
+        tree.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    #[test]
+    fn hiding_and_showing_again_matches_a_fresh_scan() {
+        for keep_files in [true, false] {
+            let shown = build(sample(), false, keep_files);
+            let hidden = build(sample(), true, keep_files);
+            let mut tree = build(sample(), true, keep_files);
+            tree.set_skip_hidden(false);
+            assert_eq!(totals(&tree), totals(&shown));
+            assert_consistent(&tree);
+            tree.set_skip_hidden(true);
+            assert_eq!(totals(&tree), totals(&hidden));
+            assert_consistent(&tree);
+        }
+    }
+
+    #[test]
+    fn hidden_entries_are_left_out_of_totals() {
+        let tree = build(sample(), true, false);
+        let root = &tree.nodes[0];
+        assert_eq!((root.size, root.file_count, root.dir_count), (120, 2, 2));
+        assert_eq!(tree.stats.skipped_entries, 2);
+        assert_eq!(tree.file_entries().len(), 2);
+    }
+
+    #[test]
+    fn file_nodes_and_entries_convert_both_ways() {
+        let nodes = build(sample(), false, true);
+        let mut tree = build(sample(), false, false);
+        assert!(tree.nodes.iter().all(|node| node.is_dir));
+        tree.set_keep_files(true);
+        assert_eq!(totals(&tree), totals(&nodes));
+        assert!(tree.file_entries().is_empty());
+        tree.set_keep_files(false);
+        assert_eq!(tree.nodes.len(), 4);
+        assert_eq!(tree.file_entries().len(), 4);
+        assert_consistent(&tree);
+    }
+
+    #[test]
+    fn a_hard_link_counts_once_whichever_name_is_shown() {
+        let mut linked = sample();
+        linked[3].inode = Some((1, 1));
+        linked.push(entry("r/a/blob", Some(50)));
+        linked[8].inode = Some((1, 1));
+        let mut tree = build(linked, true, true);
+        let size = |tree: &Tree, path: &str| {
+            let node = tree.nodes.iter().find(|node| node.path == Path::new(path));
+            node.unwrap().size
+        };
+        assert_eq!(size(&tree, "r/a/blob"), 50);
+        assert_eq!(tree.stats.hard_links, 0);
+
+        // The hidden name comes first, so it takes over the size
+        tree.set_skip_hidden(false);
+        assert_eq!(size(&tree, "r/b/.cache/blob"), 50);
+        assert_eq!(size(&tree, "r/a/blob"), 0);
+        assert_eq!(size(&tree, "r"), 177);
+        assert_eq!(tree.stats.hard_links, 1);
+
+        tree.set_skip_hidden(true);
+        assert_eq!(size(&tree, "r/a/blob"), 50);
+        assert_eq!(size(&tree, "r"), 170);
+        assert_eq!(tree.stats.hard_links, 0);
+    }
+
+    #[test]
+    fn sorting_moves_the_last_child_and_entry_marks() {
+        let mut tree = build(sample(), false, true);
+        by_name(&mut tree);
+        let marks: Vec<(&str, bool, bool)> = tree
+            .nodes
+            .iter()
+            .map(|node| (node.name.as_str(), node.is_last_child, node.is_last_entry))
+            .collect();
+        let expected = [
+            ("r", true, true),
+            ("a", false, false),
+            ("notes", false, true),
+            ("b", true, true),
+            (".cache", true, false), // The last folder, ahead of files
+            ("blob", false, true),
+            (".env", false, false),
+            ("main", false, true),
+        ];
+        assert_eq!(marks, expected);
+    }
+
+    #[test]
+    fn hidden_subtrees_go_back_below_their_folder_after_sorting() {
+        for keep_files in [true, false] {
+            let mut tree = build(sample(), true, keep_files);
+            by_name(&mut tree);
+            tree.set_skip_hidden(false);
+            assert_consistent(&tree);
+            let names: Vec<&str> = tree.nodes.iter().map(|node| node.name.as_str()).collect();
+            let expected: &[&str] = match keep_files {
+                // Put back ahead of the folder's other entries, until sorted again
+                true => &["r", "a", "notes", "b", ".cache", "blob", ".env", "main"],
+                false => &["r", "a", "b", ".cache"],
+            };
+            assert_eq!(names, expected);
+            let shown = build(sample(), false, keep_files);
+            assert_eq!(totals(&tree).1, totals(&shown).1);
+            let folder = tree.nodes.iter().position(|node| node.name == "b").unwrap();
+            let files: Vec<&str> = tree.files_in(folder).map(|(_, e)| &*e.name).collect();
+            let expected: &[&str] = if keep_files { &[] } else { &["main", ".env"] };
+            assert_eq!(files, expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be rebuilt")]
+    fn trees_without_files_cannot_show_hidden_entries() {
+        let mut tree = Tree::new(&[PathBuf::from("r")]).skip_hidden(true);
+        tree.apply(ScanEvent::Progress(sample()));
+        tree.set_skip_hidden(false);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be rebuilt")]
+    fn trees_without_file_entries_keep_their_file_nodes() {
+        let mut tree = Tree::new(&[PathBuf::from("r")]).keep_files(true);
+        tree.apply(ScanEvent::Progress(sample()));
+        tree.set_keep_files(false);
+    }
+
+    /// A folder of its own under the system temporary folder, removed when dropped
+    #[cfg(unix)]
+    struct Scratch(PathBuf);
+
+    #[cfg(unix)]
+    impl Scratch {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("planter-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Scratch(path)
+        }
+    }
+
+    #[cfg(unix)]
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_folders_are_reported_and_marked() {
+        use std::os::unix::fs::PermissionsExt;
+        let scratch = Scratch::new("unreadable");
+        let locked = scratch.0.join("locked");
+        fs::create_dir_all(scratch.0.join("open")).unwrap();
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("secret"), "hidden away").unwrap();
+        fs::write(scratch.0.join("open/file"), "0123456789").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        // Permissions do not lock out root, in which case there is no error to see
+        let readable = fs::read_dir(&locked).is_ok();
+        let tree = Scanner::new(&scratch.0).threads(1).scan();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        let tree = tree.unwrap();
+        if readable {
+            return;
+        }
+
+        let kinds: Vec<(&Path, io::ErrorKind)> = tree
+            .errors
+            .iter()
+            .map(|error| (error.path.as_path(), error.kind))
+            .collect();
+        assert_eq!(kinds, [(locked.as_path(), io::ErrorKind::PermissionDenied)]);
+        let node = |path: &Path| tree.nodes.iter().find(|node| node.path == path).unwrap();
+        assert_eq!(node(&locked).error, Some(io::ErrorKind::PermissionDenied));
+        assert_eq!(node(&scratch.0).error, None);
+        // The rest of the tree is still counted
+        assert_eq!(tree.stats.total_files, 1);
+        assert_eq!(tree.nodes[0].size, 10);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn followed_links_back_up_the_tree_are_loops() {
+        let scratch = Scratch::new("loop");
+        let folder = scratch.0.join("folder");
+        fs::create_dir(&folder).unwrap();
+        fs::write(folder.join("file"), "0123456789").unwrap();
+        std::os::unix::fs::symlink(&scratch.0, folder.join("up")).unwrap();
+        std::os::unix::fs::symlink(&folder, scratch.0.join("across")).unwrap();
+
+        let tree = Scanner::new(&scratch.0).follow_links(true).scan().unwrap();
+        let loops: Vec<&Path> = tree
+            .errors
+            .iter()
+            .filter(|error| error.message.starts_with("file system loop"))
+            .map(|error| error.path.as_path())
+            .collect();
+        // `across` is no loop by itself, but `up` below it is
+        let (up, across_up) = (folder.join("up"), scratch.0.join("across/up"));
+        assert_eq!(loops, [across_up.as_path(), up.as_path()]);
+        let node = |path: &Path| tree.nodes.iter().find(|node| node.path == path).unwrap();
+        assert_eq!(node(&folder).error, Some(io::ErrorKind::Other));
+        // The loops stay links; through `across`, the file and the link are seen again
+        assert_eq!(tree.stats.total_files, 4);
+        assert_eq!(tree.stats.hard_links, 2);
+    }
+}
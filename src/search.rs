@@ -0,0 +1,142 @@
+use globset::{GlobBuilder, GlobMatcher};
+use planter::FileNode;
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// How a search query is interpreted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    #[default]
+    Substring,
+    Glob,
+    Regex,
+}
+
+impl MatchMode {
+    pub fn next(self) -> Self {
+        match self {
+            MatchMode::Substring => MatchMode::Glob,
+            MatchMode::Glob => MatchMode::Regex,
+            MatchMode::Regex => MatchMode::Substring,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MatchMode::Substring => "substring",
+            MatchMode::Glob => "glob",
+            MatchMode::Regex => "regex",
+        }
+    }
+}
+
+/// A compiled search query. Matching is case-insensitive unless the query has uppercase letters.
+pub enum Matcher {
+    Regex(Regex),
+    Glob(GlobMatcher),
+}
+
+impl Matcher {
+    pub fn new(query: &str, mode: MatchMode) -> Result<Self, String> {
+        let ignore_case = !query.chars().any(char::is_uppercase);
+        match mode {
+            MatchMode::Substring => RegexBuilder::new(&regex::escape(query))
+                .case_insensitive(ignore_case)
+                .build()
+                .map(Matcher::Regex)
+                .map_err(|e| e.to_string()),
+            MatchMode::Regex => RegexBuilder::new(query)
+                .case_insensitive(ignore_case)
+                .build()
+                .map(Matcher::Regex)
+                .map_err(|e| e.to_string()),
+            MatchMode::Glob => GlobBuilder::new(query)
+                .case_insensitive(ignore_case)
+                .literal_separator(true)
+                .build()
+                .map(|glob| Matcher::Glob(glob.compile_matcher()))
+                .map_err(|e| e.kind().to_string()),
+        }
+    }
+
+    /// Byte range of the match within `text`; globs always cover the whole name
+    pub fn find(&self, text: &str) -> Option<Range<usize>> {
+        match self {
+            Matcher::Regex(regex) => regex.find(text).map(|m| m.range()),
+            Matcher::Glob(glob) => glob.is_match(text).then_some(0..text.len()),
+        }
+    }
+}
+
+/// State of the `/` search prompt and its results
+#[derive(Default)]
+pub struct Search {
+    pub query: String,
+    pub mode: MatchMode,
+    pub editing: bool, // The prompt has keyboard focus
+    pub filter: bool, // Hide folders that neither match nor contain a match
+    pub error: Option<String>, // Why the query failed to compile
+    pub origin: Option<usize>, // Selection when the prompt was opened, restored on cancel
+    pub matcher: Option<Matcher>,
+    pub matches: Vec<usize>, // Indices of matching nodes in tree order
+    pub keep: Vec<bool>, // Per node: matches or is an ancestor of a match
+}
+
+impl Search {
+    pub fn is_active(&self) -> bool {
+        self.matcher.is_some()
+    }
+
+    pub fn is_filtering(&self) -> bool {
+        self.filter && self.matcher.is_some()
+    }
+
+    /// Recompile the query after it or the mode changed
+    pub fn compile(&mut self) {
+        self.error = None;
+        self.matcher = None;
+        if self.query.is_empty() {
+            return;
+        }
+        match Matcher::new(&self.query, self.mode) {
+            Ok(matcher) => self.matcher = Some(matcher),
+            Err(err) => self.error = Some(err),
+        }
+    }
+
+    /// Recompute which nodes match, and which must stay visible to reach
+    /// them; files only match if `files` are shown
+    pub fn refresh(&mut self, nodes: &[FileNode], files: bool) {
+        self.matches.clear();
+        self.keep.clear();
+        let Some(matcher) = &self.matcher else {
+            return;
+        };
+
+        self.keep.resize(nodes.len(), false);
+        // Roots always stay visible
+        for (keep, node) in self.keep.iter_mut().zip(nodes) {
+            if node.parent.is_none() {
+                *keep = true;
+            }
+        }
+        for (idx, node) in nodes.iter().enumerate() {
+            if (!node.is_dir && !files) || matcher.find(&node.name).is_none() {
+                continue;
+            }
+            self.matches.push(idx);
+            let mut current = Some(idx);
+            while let Some(i) = current {
+                if self.keep[i] && i != idx {
+                    break; // Ancestors above an already kept node are kept too
+                }
+                self.keep[i] = true;
+                current = nodes[i].parent;
+            }
+        }
+    }
+
+    pub fn find_in(&self, name: &str) -> Option<Range<usize>> {
+        self.matcher.as_ref().and_then(|matcher| matcher.find(name))
+    }
+}
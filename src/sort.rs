@@ -0,0 +1,199 @@
+use crate::app::PreviewItem;
+use clap::ValueEnum;
+use planter::FileNode;
+use std::{cmp::Ordering, path::Path, time::SystemTime};
+
+/// What siblings in the tree and the preview are ordered by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// Name, ignoring case and comparing numbers by value
+    #[default]
+    Name,
+    /// Size, as shown (on disk or apparent)
+    Size,
+    /// Number of files below a folder
+    Files,
+    /// Last modification time
+    Modified,
+    /// File extension, then name
+    Extension,
+}
+
+impl SortKey {
+    pub fn next(self) -> Self {
+        match self {
+            SortKey::Name => SortKey::Size,
+            SortKey::Size => SortKey::Files,
+            SortKey::Files => SortKey::Modified,
+            SortKey::Modified => SortKey::Extension,
+            SortKey::Extension => SortKey::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Size => "size",
+            SortKey::Files => "files",
+            SortKey::Modified => "modified",
+            SortKey::Extension => "extension",
+        }
+    }
+}
+
+/// A sort key and direction; folders always come before files
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sort {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+/// The values an entry is sorted by
+struct Keys<'a> {
+    name: &'a str,
+    is_dir: bool,
+    size: u64,
+    files: usize,
+    modified: Option<SystemTime>,
+}
+
+impl<'a> Keys<'a> {
+    fn node(node: &'a FileNode, apparent: bool) -> Self {
+        Keys {
+            name: &node.name,
+            is_dir: node.is_dir,
+            size: node.usage(apparent),
+            files: node.file_count,
+            modified: node.modified,
+        }
+    }
+
+    fn item(item: &'a PreviewItem) -> Self {
+        Keys {
+            name: &item.name,
+            is_dir: item.is_dir,
+            size: item.size,
+            files: item.file_count,
+            modified: item.modified,
+        }
+    }
+}
+
+impl Sort {
+    /// Order two tree nodes; `apparent` picks the size that is shown
+    pub fn compare_nodes(self, a: &FileNode, b: &FileNode, apparent: bool) -> Ordering {
+        self.compare(Keys::node(a, apparent), Keys::node(b, apparent))
+    }
+
+    pub fn compare_items(self, a: &PreviewItem, b: &PreviewItem) -> Ordering {
+        self.compare(Keys::item(a), Keys::item(b))
+    }
+
+    fn compare(self, a: Keys, b: Keys) -> Ordering {
+        let primary = match self.key {
+            SortKey::Name => natural_cmp(a.name, b.name),
+            SortKey::Size => a.size.cmp(&b.size),
+            SortKey::Files => a.files.cmp(&b.files),
+            SortKey::Modified => a.modified.cmp(&b.modified),
+            SortKey::Extension => extension(a.name).cmp(&extension(b.name)),
+        };
+        let primary = if self.descending {
+            primary.reverse()
+        } else {
+            primary
+        };
+        // Folders first, and ties broken by name so the order is stable
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then(primary)
+            .then_with(|| natural_cmp(a.name, b.name))
+    }
+}
+
+/// Lowercased extension, empty for names without one
+fn extension(name: &str) -> String {
+    Path::new(name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Compare names ignoring case, with runs of digits compared by value (`file2` before `file10`)
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return a.cmp(b), // Equal apart from case: fall back to bytes
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = digit_run(&mut a_chars);
+                let y = digit_run(&mut b_chars);
+                // Without leading zeros, a longer run is a larger number
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+fn digit_run(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut run = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        run.push(c);
+    }
+    run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::natural_cmp;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn numbers_compare_by_value() {
+        assert_eq!(natural_cmp("file2", "file10"), Ordering::Less);
+        assert_eq!(natural_cmp("file10", "file9"), Ordering::Greater);
+        assert_eq!(natural_cmp("v1.10.0", "v1.9.3"), Ordering::Greater);
+        // Longer than any integer type
+        assert_eq!(
+            natural_cmp("x3", "x123456789012345678901234567890"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn leading_zeros_only_break_ties() {
+        assert_eq!(natural_cmp("a007", "a10"), Ordering::Less);
+        assert_eq!(natural_cmp("a010", "a9"), Ordering::Greater);
+        assert_eq!(natural_cmp("a01", "a1"), Ordering::Less);
+        assert_eq!(natural_cmp("a01b", "a1c"), Ordering::Less);
+    }
+
+    #[test]
+    fn case_only_breaks_ties() {
+        assert_eq!(natural_cmp("apple", "Banana"), Ordering::Less);
+        assert_eq!(natural_cmp("Zebra", "apple"), Ordering::Greater);
+        assert_eq!(natural_cmp("File", "file"), Ordering::Less);
+        assert_eq!(natural_cmp("same", "same"), Ordering::Equal);
+    }
+
+    #[test]
+    fn prefixes_come_first() {
+        assert_eq!(natural_cmp("abc", "abcd"), Ordering::Less);
+        assert_eq!(natural_cmp("a1", "a"), Ordering::Greater);
+        assert_eq!(natural_cmp("", "a"), Ordering::Less);
+    }
+}
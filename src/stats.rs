@@ -0,0 +1,97 @@
+use std::time::{Duration, SystemTime};
+
+/// Number of time buckets for the file timeline histogram
+pub const TIMELINE_BUCKETS: usize = 8;
+
+/// Which file timestamps the timeline histogram counts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimelineSource {
+    #[default]
+    Created,
+    Modified, // The filesystem records no creation times, as many Linux ones don't
+    Mixed, // Creation times where there are any, modification times for the other files
+}
+
+impl TimelineSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            TimelineSource::Created => "created",
+            TimelineSource::Modified => "modified",
+            TimelineSource::Mixed => "created or modified",
+        }
+    }
+}
+
+/// Aggregate statistics gathered while scanning a directory tree.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    pub total_files: usize,
+    pub total_dirs: usize,
+    pub total_size: u64, // Apparent size of all files
+    pub total_disk_size: u64, // Space allocated on disk for all files and folders, like `du`
+    pub hard_links: usize, // Extra names of files that were already counted (hard links, or followed links), left out of the sizes
+    pub max_depth: usize,
+    pub skipped_entries: usize, // Left out by ignore files, hidden, exclude and include filters; a folder counts once
+    pub broken_links: usize, // Symbolic links whose target does not exist
+    pub file_timeline: Vec<usize>, // Histogram buckets counting files per time period for timeline display
+    pub timeline_source: TimelineSource, // Which timestamps the timeline counts
+    pub oldest_file_time: Option<SystemTime>, // Oldest file time counted in the timeline
+    pub newest_file_time: Option<SystemTime>, // Newest file time counted in the timeline
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Stats {
+            file_timeline: vec![0; TIMELINE_BUCKETS],
+            ..Default::default()
+        }
+    }
+
+    /// The total apparent size, or the total space allocated on disk
+    pub fn usage(&self, apparent: bool) -> u64 {
+        if apparent {
+            self.total_size
+        } else {
+            self.total_disk_size
+        }
+    }
+
+    /// Build the timeline histogram from the file times collected during a scan.
+    ///
+    /// The times are sorted in place. The oldest and newest times are stored
+    /// alongside the histogram so the range can be labelled when displayed.
+    pub fn build_timeline(&mut self, file_times: &mut [SystemTime]) {
+        if file_times.is_empty() {
+            self.file_timeline = vec![0; TIMELINE_BUCKETS];
+            self.oldest_file_time = None;
+            self.newest_file_time = None;
+            return;
+        }
+
+        file_times.sort();
+        let oldest = file_times[0];
+        let newest = file_times[file_times.len() - 1];
+
+        // Store the oldest and newest times for display
+        self.oldest_file_time = Some(oldest);
+        self.newest_file_time = Some(newest);
+
+        let time_range = newest.duration_since(oldest).unwrap_or(Duration::from_secs(0));
+
+        let mut buckets = vec![0; TIMELINE_BUCKETS];
+        // If all files have the same timestamp, put them all in one bucket
+        if time_range.as_secs() == 0 {
+            buckets[0] = file_times.len();
+        } else {
+            // Calculate bucket size, ensuring at least 1 second per bucket
+            let bucket_size = (time_range.as_secs() / TIMELINE_BUCKETS as u64).max(1);
+
+            for time in file_times.iter() {
+                let age = time.duration_since(oldest).unwrap_or(Duration::from_secs(0));
+                let bucket_idx = ((age.as_secs() / bucket_size) as usize).min(TIMELINE_BUCKETS - 1);
+                buckets[bucket_idx] += 1;
+            }
+        }
+        self.file_timeline = buckets;
+    }
+}
@@ -0,0 +1,236 @@
+use planter::FileNode;
+use ratatui::layout::Rect;
+use std::{collections::HashMap, f64::consts::TAU};
+
+pub const MAX_RINGS: usize = 6; // Levels drawn around the center
+
+/// A node's arc: on ring `ring` (1 is next to the center), from `start` to
+/// `end`, in turns clockwise from the top
+#[derive(Clone, Copy, Debug)]
+pub struct Segment {
+    pub node: usize,
+    pub ring: usize,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Braille dots of a drawn chart, by where they fall, as x and y from the
+/// bottom left
+#[derive(Default)]
+pub struct Dots {
+    pub center: Vec<(f64, f64)>,
+    pub segments: Vec<Vec<(f64, f64)>>, // By segment position
+}
+
+/// The arcs of a sunburst chart
+#[derive(Default)]
+pub struct Sunburst {
+    pub segments: Vec<Segment>,
+    rings: Vec<Vec<usize>>, // Segment positions on each ring, clockwise
+}
+
+impl Sunburst {
+    /// Arcs for the nodes below `root` (or below all roots), each spanning its
+    /// share of its parent's arc. Nodes that `shown` rejects, or that take no
+    /// space, are left out together with everything below them.
+    pub fn new(
+        nodes: &[FileNode],
+        root: Option<usize>,
+        apparent: bool,
+        shown: impl Fn(&FileNode) -> bool,
+    ) -> Self {
+        Sunburst::from_segments(layout(nodes, root, apparent, shown))
+    }
+
+    fn from_segments(segments: Vec<Segment>) -> Self {
+        let mut rings = vec![Vec::new(); MAX_RINGS + 1];
+        for (pos, segment) in segments.iter().enumerate() {
+            rings[segment.ring].push(pos);
+        }
+        for ring in &mut rings {
+            ring.sort_by(|&a, &b| segments[a].start.total_cmp(&segments[b].start));
+        }
+        Sunburst { segments, rings }
+    }
+
+    /// Segment positions on a ring, clockwise from the top
+    pub fn ring(&self, ring: usize) -> &[usize] {
+        self.rings.get(ring).map_or(&[], Vec::as_slice)
+    }
+
+    /// Rings that have segments
+    pub fn depth(&self) -> usize {
+        self.rings
+            .iter()
+            .rposition(|ring| !ring.is_empty())
+            .unwrap_or(0)
+    }
+
+    /// The segment at `angle` on `ring`
+    pub fn hit(&self, ring: usize, angle: f64) -> Option<usize> {
+        let ring = self.ring(ring);
+        let pos = ring.partition_point(|&seg| self.segments[seg].end <= angle);
+        ring.get(pos)
+            .copied()
+            .filter(|&seg| self.segments[seg].start <= angle)
+    }
+
+    /// Width of a ring in braille dots when the chart fills `area`; the
+    /// center disc is as wide as a ring
+    pub fn ring_width(&self, area: Rect) -> f64 {
+        // Two dots per cell across and four down, so dots are about square
+        let radius = (area.width as f64).min(area.height as f64 * 2.0);
+        radius / (self.depth().max(1) as f64 + 1.0)
+    }
+
+    /// Ring and segment at an offset in dots from the center (y up); ring 0
+    /// is the center, and `None` is outside the chart
+    pub fn locate(&self, dx: f64, dy: f64, area: Rect) -> Option<(usize, Option<usize>)> {
+        let ring = (dx.hypot(dy) / self.ring_width(area)) as usize;
+        match ring {
+            0 => Some((0, None)),
+            _ if ring > self.depth() => None,
+            _ => Some((ring, self.hit(ring, angle(dx, dy)))),
+        }
+    }
+
+    /// The dots of the chart filling `area`, leaving a thin gap between
+    /// rings and between neighbouring arcs
+    pub fn dots(&self, area: Rect) -> Dots {
+        let ring_width = self.ring_width(area);
+        let (width, height) = (area.width as usize * 2, area.height as usize * 4);
+        let mut dots = Dots {
+            center: Vec::new(),
+            segments: vec![Vec::new(); self.segments.len()],
+        };
+        for y in 0..height {
+            for x in 0..width {
+                let dx = x as f64 + 0.5 - width as f64 / 2.0;
+                let dy = height as f64 / 2.0 - (y as f64 + 0.5);
+                let point = (x as f64, (height - 1 - y) as f64);
+                let radius = dx.hypot(dy);
+                match self.locate(dx, dy, area) {
+                    Some((0, _)) => dots.center.push(point),
+                    Some((ring, Some(seg))) if radius - ring as f64 * ring_width >= 0.8 => {
+                        let into = angle(dx, dy) - self.segments[seg].start;
+                        if into * TAU * radius >= 0.8 {
+                            dots.segments[seg].push(point);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        dots
+    }
+
+    pub fn position(&self, node: usize) -> Option<usize> {
+        self.segments
+            .iter()
+            .position(|segment| segment.node == node)
+    }
+}
+
+fn layout(
+    nodes: &[FileNode],
+    root: Option<usize>,
+    apparent: bool,
+    shown: impl Fn(&FileNode) -> bool,
+) -> Vec<Segment> {
+    // Next free angle and angle per byte of each placed folder
+    let mut open: HashMap<Option<usize>, (f64, f64)> = HashMap::new();
+    let (range, depth) = match root {
+        Some(root) => {
+            let depth = nodes[root].depth;
+            let end = (root + 1..nodes.len())
+                .find(|&idx| nodes[idx].depth <= depth)
+                .unwrap_or(nodes.len());
+            (root + 1..end, depth + 1)
+        }
+        None => (0..nodes.len(), 0),
+    };
+    let total: u64 = match root {
+        Some(root) => nodes[root].usage(apparent),
+        None => nodes
+            .iter()
+            .filter(|node| node.parent.is_none())
+            .map(|node| node.usage(apparent))
+            .sum(),
+    };
+    if total == 0 {
+        return Vec::new();
+    }
+    open.insert(root, (0.0, 1.0 / total as f64));
+
+    let mut segments = Vec::new();
+    for idx in range {
+        let node = &nodes[idx];
+        let ring = node.depth + 1 - depth;
+        let Some((cursor, per_byte)) = open.get_mut(&node.parent) else {
+            continue;
+        };
+        let size = node.usage(apparent);
+        if ring > MAX_RINGS || size == 0 || !shown(node) {
+            continue;
+        }
+        let start = *cursor;
+        let end = start + size as f64 * *per_byte;
+        *cursor = end;
+        segments.push(Segment {
+            node: idx,
+            ring,
+            start,
+            end,
+        });
+        if node.is_dir {
+            open.insert(Some(idx), (start, (end - start) / size as f64));
+        }
+    }
+    segments
+}
+
+/// Angle of a point in turns clockwise from the top, given its offset from
+/// the center with y pointing up
+pub fn angle(dx: f64, dy: f64) -> f64 {
+    (dx.atan2(dy) / TAU).rem_euclid(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(node: usize, ring: usize, start: f64, end: f64) -> Segment {
+        Segment {
+            node,
+            ring,
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn dots_fall_in_their_arc() {
+        let sunburst = Sunburst::from_segments(vec![
+            segment(1, 1, 0.0, 0.75),
+            segment(2, 1, 0.75, 1.0),
+            segment(3, 2, 0.0, 0.5),
+        ]);
+        let area = Rect::new(0, 0, 40, 20);
+        let dots = sunburst.dots(area);
+        let (width, height) = (80.0, 80.0);
+        let locate = |&(x, y): &(f64, f64)| {
+            let (dx, dy) = (x + 0.5 - width / 2.0, y + 0.5 - height / 2.0);
+            sunburst.locate(dx, dy, area)
+        };
+        assert!(!dots.center.is_empty());
+        assert!(dots.center.iter().all(|dot| locate(dot) == Some((0, None))));
+        for (seg, coords) in dots.segments.iter().enumerate() {
+            let at = Some((sunburst.segments[seg].ring, Some(seg)));
+            assert!(coords.iter().all(|dot| locate(dot) == at));
+        }
+        // Three quarters of a ring take about three times the dots of the last quarter
+        let counts: Vec<usize> = dots.segments.iter().map(Vec::len).collect();
+        assert!(counts[0] > counts[1] * 2 && counts[1] > 0, "{counts:?}");
+        assert!(counts[2] > 0, "{counts:?}");
+    }
+}
@@ -0,0 +1,95 @@
+use clap::ValueEnum;
+use ratatui::style::Color;
+
+/// Built-in color themes, selected with `--theme`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ThemeName {
+    /// Bright colors for dark terminals
+    #[default]
+    Default,
+    /// Darker colors for light terminals
+    Light,
+    /// Shades of gray only
+    Mono,
+}
+
+/// Colors used by the UI, named by what they are used for
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    pub border: Color, // Panel borders, tree connectors and bars
+    pub folder: Color,
+    pub text: Color,
+    pub muted: Color, // Secondary details such as counts and hints
+    pub selection: Color, // Background of the selected row
+    pub highlight: Color, // Sizes, headings and search matches
+    pub accent: Color,
+    pub error: Color,
+    pub match_text: Color, // Foreground of search matches on the highlight color
+    // Syntax highlighting in file previews; comments use `muted`
+    pub keyword: Color,
+    pub string: Color,
+    pub constant: Color,
+    pub function: Color,
+    pub type_name: Color,
+}
+
+impl Theme {
+    pub fn new(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Default => Theme {
+                border: Color::Green,
+                folder: Color::Cyan,
+                text: Color::White,
+                muted: Color::DarkGray,
+                selection: Color::DarkGray,
+                highlight: Color::Yellow,
+                accent: Color::Blue,
+                error: Color::Red,
+                match_text: Color::Black,
+                keyword: Color::Magenta,
+                string: Color::Green,
+                constant: Color::LightRed,
+                function: Color::Blue,
+                type_name: Color::Yellow,
+            },
+            ThemeName::Light => Theme {
+                border: Color::Green,
+                folder: Color::Blue,
+                text: Color::Black,
+                muted: Color::DarkGray,
+                selection: Color::Gray,
+                highlight: Color::Magenta,
+                accent: Color::Cyan,
+                error: Color::Red,
+                match_text: Color::White,
+                keyword: Color::Magenta,
+                string: Color::Green,
+                constant: Color::Red,
+                function: Color::Blue,
+                type_name: Color::Cyan,
+            },
+            ThemeName::Mono => Theme {
+                border: Color::Gray,
+                folder: Color::White,
+                text: Color::White,
+                muted: Color::DarkGray,
+                selection: Color::DarkGray,
+                highlight: Color::White,
+                accent: Color::Gray,
+                error: Color::White,
+                match_text: Color::Black,
+                keyword: Color::White,
+                string: Color::Gray,
+                constant: Color::Gray,
+                function: Color::White,
+                type_name: Color::White,
+            },
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new(ThemeName::Default)
+    }
+}
@@ -0,0 +1,499 @@
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use planter::{TimelineSource, Tree};
+use std::{ops::Range, time::SystemTime};
+
+pub const ROWS: usize = 8; // Buckets shown at once in the Info panel
+const MIN_ZOOMED: usize = 4; // Zooming in picks a finer granularity until there are this many buckets
+
+/// Which timestamp of the files the timeline counts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeField {
+    #[default]
+    Created,
+    Modified,
+    Accessed,
+}
+
+impl TimeField {
+    pub fn next(self) -> Self {
+        match self {
+            TimeField::Created => TimeField::Modified,
+            TimeField::Modified => TimeField::Accessed,
+            TimeField::Accessed => TimeField::Created,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TimeField::Created => "created",
+            TimeField::Modified => "modified",
+            TimeField::Accessed => "accessed",
+        }
+    }
+
+    /// The timestamp of a file, and whether its modification time stands in for it
+    fn of(self, times: [Option<SystemTime>; 3]) -> (Option<SystemTime>, bool) {
+        let [created, modified, accessed] = times;
+        match self {
+            // Many Linux filesystems record no creation times
+            TimeField::Created => (
+                created.or(modified),
+                created.is_none() && modified.is_some(),
+            ),
+            TimeField::Modified => (modified, false),
+            TimeField::Accessed => (accessed, false),
+        }
+    }
+}
+
+/// How much time one bar of the timeline covers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Granularity {
+    Day,
+    Week,
+    #[default]
+    Month,
+    Year,
+}
+
+impl Granularity {
+    pub fn next(self) -> Self {
+        match self {
+            Granularity::Day => Granularity::Week,
+            Granularity::Week => Granularity::Month,
+            Granularity::Month => Granularity::Year,
+            Granularity::Year => Granularity::Day,
+        }
+    }
+
+    fn finer(self) -> Option<Self> {
+        match self {
+            Granularity::Day => None,
+            Granularity::Week => Some(Granularity::Day),
+            Granularity::Month => Some(Granularity::Week),
+            Granularity::Year => Some(Granularity::Month),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Granularity::Day => "day",
+            Granularity::Week => "week",
+            Granularity::Month => "month",
+            Granularity::Year => "year",
+        }
+    }
+
+    /// Number of the bucket a day falls in; consecutive buckets have consecutive numbers
+    fn key(self, date: NaiveDate) -> i64 {
+        let days = date.num_days_from_ce() as i64;
+        match self {
+            Granularity::Day => days,
+            // Day 1 of the common era was a Monday, so weeks start on Mondays
+            Granularity::Week => (days - 1).div_euclid(7),
+            Granularity::Month => date.year() as i64 * 12 + date.month0() as i64,
+            Granularity::Year => date.year() as i64,
+        }
+    }
+
+    /// First day of a bucket
+    fn start(self, key: i64) -> NaiveDate {
+        let date = match self {
+            Granularity::Day => i32::try_from(key)
+                .ok()
+                .and_then(NaiveDate::from_num_days_from_ce_opt),
+            Granularity::Week => i32::try_from(key * 7 + 1)
+                .ok()
+                .and_then(NaiveDate::from_num_days_from_ce_opt),
+            Granularity::Month => i32::try_from(key.div_euclid(12))
+                .ok()
+                .and_then(|year| NaiveDate::from_ymd_opt(year, key.rem_euclid(12) as u32 + 1, 1)),
+            Granularity::Year => i32::try_from(key)
+                .ok()
+                .and_then(|year| NaiveDate::from_ymd_opt(year, 1, 1)),
+        };
+        date.unwrap_or(if key < 0 {
+            NaiveDate::MIN
+        } else {
+            NaiveDate::MAX
+        })
+    }
+
+    fn format(self, key: i64) -> String {
+        let start = self.start(key);
+        match self {
+            Granularity::Day => start.format("%Y-%m-%d").to_string(),
+            Granularity::Week => start.format("%G-W%V").to_string(),
+            Granularity::Month => start.format("%Y-%m").to_string(),
+            Granularity::Year => start.format("%Y").to_string(),
+        }
+    }
+}
+
+/// Files per period of time, the buckets picked in the Info panel, and the
+/// period the tree and preview are filtered to
+#[derive(Default)]
+pub struct Timeline {
+    pub field: TimeField,
+    pub granularity: Granularity,
+    pub zoom: Option<Range<NaiveDate>>, // Only files dated within are counted
+    pub first: i64,                     // Key of the first bucket
+    pub counts: Vec<usize>,             // Files per bucket, oldest first
+    pub undated: usize,                 // Files without the timestamp
+    pub source: TimelineSource, // For creation times, whether modification times stand in for them
+    pub cursor: Option<NaiveDate>, // A day in the bucket under the cursor; `None` follows the newest
+    pub anchor: Option<NaiveDate>, // Other end of a range of buckets being picked
+    pub scroll: usize,             // First bucket shown
+    pub filter: Option<Range<NaiveDate>>, // Only files dated within, and the folders above them, are shown
+    pub keep: Vec<bool>, // Per node: passes the filter or is above a file that does
+    dates: Vec<Option<NaiveDate>>, // Per node, in local time; `None` for folders
+    entry_dates: Vec<Option<NaiveDate>>, // Per file entry of the tree
+    files: usize,
+    stand_ins: usize, // Files counted by modification time for lack of a creation time
+}
+
+impl Timeline {
+    /// Catch up with the nodes and file entries a scan appended since the last call
+    pub fn refresh(&mut self, tree: &Tree) {
+        let (nodes, entries) = (&tree.nodes, tree.file_entries());
+        if self.dates.len() > nodes.len() || self.entry_dates.len() > entries.len() {
+            self.reset(tree);
+            return;
+        }
+        let (from, from_entries) = (self.dates.len(), self.entry_dates.len());
+        for node in &nodes[from..] {
+            let date = match node.is_dir {
+                true => None,
+                false => self.count_file([node.created, node.modified, node.accessed]),
+            };
+            self.dates.push(date);
+        }
+        for entry in &entries[from_entries..] {
+            let date = self.count_file([entry.created, entry.modified, entry.accessed]);
+            self.entry_dates.push(date);
+        }
+        self.source = match self.stand_ins {
+            0 => TimelineSource::Created,
+            n if n + self.undated == self.files => TimelineSource::Modified,
+            _ => TimelineSource::Mixed,
+        };
+        self.count(from, from_entries);
+        self.scroll_to_cursor();
+        self.filter_from(tree, from, from_entries);
+    }
+
+    /// Count a file in the totals, and return the date it is bucketed by
+    fn count_file(&mut self, times: [Option<SystemTime>; 3]) -> Option<NaiveDate> {
+        let (time, stand_in) = self.field.of(times);
+        let date = time.map(|time| DateTime::<Local>::from(time).date_naive());
+        self.files += 1;
+        self.undated += usize::from(date.is_none());
+        self.stand_ins += usize::from(stand_in);
+        date
+    }
+
+    /// Start over after the nodes were reordered or replaced
+    pub fn reset(&mut self, tree: &Tree) {
+        self.dates.clear();
+        self.entry_dates.clear();
+        self.files = 0;
+        self.undated = 0;
+        self.stand_ins = 0;
+        self.counts.clear();
+        self.keep.clear();
+        self.refresh(tree);
+    }
+
+    /// Count the dates again after the buckets changed
+    fn recount(&mut self) {
+        self.counts.clear();
+        self.count(0, 0);
+        self.scroll_to_cursor();
+    }
+
+    /// Add the dates of the nodes from `from` and the file entries from
+    /// `from_entries` on to the buckets, widening the timeline to fit them
+    fn count(&mut self, from: usize, from_entries: usize) {
+        let granularity = self.granularity;
+        // A zoomed timeline covers the whole zoomed period, even where it is empty
+        if let (Some(zoom), true) = (&self.zoom, self.counts.is_empty()) {
+            let last = granularity.key(zoom.end.pred_opt().unwrap_or(zoom.end));
+            self.first = granularity.key(zoom.start);
+            self.counts = vec![0; (last - self.first + 1) as usize];
+        }
+        let dates = self.dates[from..]
+            .iter()
+            .chain(&self.entry_dates[from_entries..]);
+        for &date in dates.flatten() {
+            if self.zoom.as_ref().is_some_and(|zoom| !zoom.contains(&date)) {
+                continue;
+            }
+            let key = granularity.key(date);
+            if self.counts.is_empty() {
+                self.first = key;
+            } else if key < self.first {
+                let earlier = (self.first - key) as usize;
+                self.counts.splice(..0, std::iter::repeat_n(0, earlier));
+                self.first = key;
+            }
+            let bucket = (key - self.first) as usize;
+            if bucket >= self.counts.len() {
+                self.counts.resize(bucket + 1, 0);
+            }
+            self.counts[bucket] += 1;
+        }
+    }
+
+    /// Recompute which nodes the filter keeps
+    fn refilter(&mut self, tree: &Tree) {
+        self.keep.clear();
+        self.filter_from(tree, 0, 0);
+    }
+
+    /// Mark the nodes from `from` on that the filter keeps, with the folders
+    /// above them and above the kept file entries from `from_entries` on
+    fn filter_from(&mut self, tree: &Tree, from: usize, from_entries: usize) {
+        let Some(filter) = &self.filter else {
+            return;
+        };
+        let nodes = &tree.nodes;
+        self.keep.resize(nodes.len(), false);
+        let mut kept = Vec::new();
+        for (idx, node) in nodes.iter().enumerate().skip(from) {
+            // Roots always stay visible
+            if node.parent.is_none() {
+                self.keep[idx] = true;
+            }
+            if self.dates[idx].is_some_and(|date| filter.contains(&date)) {
+                kept.push(Some(idx));
+            }
+        }
+        let entries = tree
+            .file_entries()
+            .iter()
+            .zip(&self.entry_dates)
+            .skip(from_entries);
+        for (entry, date) in entries {
+            if date.is_some_and(|date| filter.contains(&date)) {
+                kept.push(entry.parent);
+            }
+        }
+        for mut current in kept {
+            let mut first = true;
+            while let Some(i) = current {
+                if self.keep[i] && !first {
+                    break; // Folders above a kept one are kept already
+                }
+                self.keep[i] = true;
+                current = nodes[i].parent;
+                first = false;
+            }
+        }
+    }
+
+    /// Whether the filter keeps a file entry of the tree
+    pub fn keeps_entry(&self, idx: usize) -> bool {
+        let date = self.entry_dates.get(idx).copied().flatten();
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| date.is_some_and(|date| filter.contains(&date)))
+    }
+
+    pub fn is_filtering(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    pub fn keeps(&self, idx: usize) -> bool {
+        self.filter.is_none() || self.keep.get(idx).copied().unwrap_or(false)
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter = None;
+        self.keep.clear();
+    }
+
+    /// Bucket of a day, clamped to the timeline
+    fn bucket_of(&self, date: NaiveDate) -> usize {
+        let last = self.counts.len().saturating_sub(1) as i64;
+        (self.granularity.key(date) - self.first).clamp(0, last) as usize
+    }
+
+    /// First day of a bucket; one past the last is where the timeline ends
+    fn date_of(&self, bucket: usize) -> NaiveDate {
+        self.granularity.start(self.first + bucket as i64)
+    }
+
+    /// What the counted timestamps are
+    pub fn field_label(&self) -> &'static str {
+        match self.field {
+            TimeField::Created => self.source.label(),
+            field => field.label(),
+        }
+    }
+
+    pub fn label(&self, bucket: usize) -> String {
+        self.granularity.format(self.first + bucket as i64)
+    }
+
+    pub fn cursor(&self) -> usize {
+        match self.cursor {
+            Some(date) => self.bucket_of(date),
+            None => self.counts.len().saturating_sub(1),
+        }
+    }
+
+    /// Buckets between the anchor and the cursor, or just the cursor's
+    pub fn selection(&self) -> Range<usize> {
+        let cursor = self.cursor();
+        let anchor = self.anchor.map_or(cursor, |date| self.bucket_of(date));
+        cursor.min(anchor)..cursor.max(anchor) + 1
+    }
+
+    /// The days a range of buckets covers
+    pub fn period(&self, buckets: Range<usize>) -> Range<NaiveDate> {
+        self.date_of(buckets.start)..self.date_of(buckets.end)
+    }
+
+    /// Move the cursor by `delta` buckets, optionally growing the selection
+    pub fn move_cursor(&mut self, delta: isize, extend: bool) {
+        if self.counts.is_empty() {
+            return;
+        }
+        let from = self.cursor();
+        if !extend {
+            self.anchor = None;
+        } else if self.anchor.is_none() {
+            self.anchor = Some(self.date_of(from));
+        }
+        let to = from.saturating_add_signed(delta).min(self.counts.len() - 1);
+        self.cursor = (to + 1 < self.counts.len()).then(|| self.date_of(to));
+        self.scroll_to_cursor();
+    }
+
+    fn scroll_to_cursor(&mut self) {
+        let cursor = self.cursor();
+        self.scroll = self
+            .scroll
+            .clamp((cursor + 1).saturating_sub(ROWS), cursor)
+            .min(self.counts.len().saturating_sub(ROWS));
+    }
+
+    /// Filter to the selected buckets, or drop the filter if it is already on them
+    pub fn toggle_filter(&mut self, tree: &Tree) {
+        if self.counts.is_empty() {
+            return;
+        }
+        let period = self.period(self.selection());
+        self.filter = (self.filter.as_ref() != Some(&period)).then_some(period);
+        self.anchor = None;
+        self.refilter(tree);
+    }
+
+    /// Spread the selected buckets over the whole timeline, in finer buckets if they are few
+    pub fn zoom_in(&mut self) {
+        if self.counts.is_empty() {
+            return;
+        }
+        let period = self.period(self.selection());
+        self.cursor = Some(period.start);
+        self.anchor = None;
+        self.zoom = Some(period);
+        self.recount();
+        while self.counts.len() < MIN_ZOOMED {
+            let Some(finer) = self.granularity.finer() else {
+                break;
+            };
+            self.granularity = finer;
+            self.recount();
+        }
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom = None;
+        self.recount();
+    }
+
+    pub fn cycle_granularity(&mut self) {
+        self.granularity = self.granularity.next();
+        self.anchor = None;
+        self.recount();
+    }
+
+    /// Count another timestamp; the filter keeps its period
+    pub fn cycle_field(&mut self, tree: &Tree) {
+        self.field = self.field.next();
+        self.anchor = None;
+        self.reset(tree);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Weekday;
+
+    const ALL: [Granularity; 4] = [
+        Granularity::Day,
+        Granularity::Week,
+        Granularity::Month,
+        Granularity::Year,
+    ];
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn buckets_start_where_their_days_fall() {
+        let mut day = date(-1, 12, 20);
+        while day < date(2, 1, 20) {
+            for granularity in ALL {
+                let key = granularity.key(day);
+                let start = granularity.start(key);
+                assert!(start <= day, "{granularity:?} {day}");
+                assert_eq!(granularity.key(start), key, "{granularity:?} {day}");
+                // The day before a bucket starts is in the one before it
+                let before = start.pred_opt().unwrap();
+                assert_eq!(granularity.key(before), key - 1, "{granularity:?} {day}");
+            }
+            day = day.succ_opt().unwrap();
+        }
+        for day in [date(1970, 1, 1), date(2024, 2, 29), date(2026, 10, 17)] {
+            for granularity in ALL {
+                let key = granularity.key(day);
+                assert_eq!(granularity.key(granularity.start(key)), key);
+                assert!(granularity.start(key + 1) > day);
+            }
+        }
+    }
+
+    #[test]
+    fn weeks_start_on_mondays() {
+        let week = Granularity::Week;
+        let mut day = date(2023, 12, 1);
+        while day < date(2024, 3, 1) {
+            assert_eq!(week.start(week.key(day)).weekday(), Weekday::Mon, "{day}");
+            day = day.succ_opt().unwrap();
+        }
+        // 2024 began on a Monday
+        assert_eq!(week.key(date(2024, 1, 1)), week.key(date(2024, 1, 7)));
+        assert_eq!(week.key(date(2024, 1, 8)), week.key(date(2024, 1, 1)) + 1);
+        assert_eq!(week.key(date(2023, 12, 31)), week.key(date(2024, 1, 1)) - 1);
+        assert_eq!(week.format(week.key(date(2024, 1, 3))), "2024-W01");
+    }
+
+    #[test]
+    fn months_and_years_roll_over() {
+        let month = Granularity::Month;
+        assert_eq!(
+            month.start(month.key(date(2024, 12, 31)) + 1),
+            date(2025, 1, 1)
+        );
+        assert_eq!(month.format(month.key(date(2024, 2, 29))), "2024-02");
+        let year = Granularity::Year;
+        assert_eq!(year.start(year.key(date(2024, 7, 4))), date(2024, 1, 1));
+        // Keys out of the calendar's range clamp instead of panicking
+        assert_eq!(Granularity::Day.start(i64::MAX), NaiveDate::MAX);
+        assert_eq!(Granularity::Day.start(i64::MIN / 8), NaiveDate::MIN);
+    }
+}
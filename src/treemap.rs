@@ -0,0 +1,213 @@
+use planter::FileNode;
+use ratatui::layout::Rect;
+use std::{
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// What the treemap tiles are colored by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorBy {
+    #[default]
+    Kind,
+    Age,
+}
+
+impl ColorBy {
+    pub fn next(self) -> Self {
+        match self {
+            ColorBy::Kind => ColorBy::Age,
+            ColorBy::Age => ColorBy::Kind,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorBy::Kind => "type",
+            ColorBy::Age => "age",
+        }
+    }
+}
+
+/// Rough file types, guessed from the extension
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Folder,
+    Code,
+    Document,
+    Media,
+    Archive,
+    Other,
+}
+
+impl Kind {
+    pub fn of(node: &FileNode) -> Self {
+        if node.is_dir {
+            return Kind::Folder;
+        }
+        let ext = Path::new(&node.name)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "go" | "py" | "rb" | "js" | "ts" | "jsx"
+            | "tsx" | "java" | "kt" | "swift" | "sh" | "lua" | "toml" | "json" | "yaml" | "yml"
+            | "xml" | "html" | "css" => Kind::Code,
+            "txt" | "md" | "rst" | "pdf" | "doc" | "docx" | "odt" | "csv" | "log" => Kind::Document,
+            "png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "mp3" | "flac" | "ogg" | "wav"
+            | "mp4" | "mkv" | "webm" | "mov" => Kind::Media,
+            "zip" | "tar" | "gz" | "tgz" | "xz" | "bz2" | "zst" | "7z" | "rar" | "deb" | "rpm"
+            | "jar" => Kind::Archive,
+            _ => Kind::Other,
+        }
+    }
+}
+
+/// How long ago a node was modified: 0 within a day, 1 within a month,
+/// 2 within a year, 3 for older or unknown times
+pub fn age_class(node: &FileNode, now: SystemTime) -> usize {
+    const DAY: u64 = 24 * 60 * 60;
+    let Some(age) = node.modified.and_then(|time| now.duration_since(time).ok()) else {
+        // Unknown, or in the future
+        return if node.modified.is_some() { 0 } else { 3 };
+    };
+    [DAY, 30 * DAY, 365 * DAY]
+        .iter()
+        .position(|&limit| age < Duration::from_secs(limit))
+        .unwrap_or(3)
+}
+
+/// Split `area` into one rectangle per size, keeping them as close to square
+/// as possible (the squarified layout of Bruls, Huizing and van Wijk).
+///
+/// `sizes` must be sorted largest first. Rectangles come back in the same
+/// order; the smallest ones may end up empty after rounding to cells.
+pub fn squarify(sizes: &[u64], area: Rect) -> Vec<Rect> {
+    let total: u64 = sizes.iter().sum();
+    if total == 0 || area.is_empty() {
+        return vec![Rect::default(); sizes.len()];
+    }
+    // Cells are about twice as tall as wide, so lay out in square units
+    let (width, height) = (area.width as f64, area.height as f64 * 2.0);
+    let scale = width * height / total as f64;
+    let areas: Vec<f64> = sizes.iter().map(|&size| size as f64 * scale).collect();
+
+    let mut free = (0.0, 0.0, width, height); // x, y, width, height still to fill
+    let mut placed = Vec::with_capacity(sizes.len());
+    let mut start = 0;
+    while start < areas.len() {
+        let side = free.2.min(free.3);
+        // Grow the row while that makes its worst aspect ratio better
+        let mut end = start + 1;
+        while end < areas.len()
+            && worst(&areas[start..=end], side) <= worst(&areas[start..end], side)
+        {
+            end += 1;
+        }
+        let row = &areas[start..end];
+        let sum: f64 = row.iter().sum();
+        let (x, y, w, h) = free;
+        if w >= h {
+            // A column along the left edge
+            let column = if h > 0.0 { sum / h } else { 0.0 };
+            let mut top = y;
+            for &item in row {
+                let size = if column > 0.0 { item / column } else { 0.0 };
+                placed.push((x, top, column, size));
+                top += size;
+            }
+            free = (x + column, y, w - column, h);
+        } else {
+            // A row along the top edge
+            let band = if w > 0.0 { sum / w } else { 0.0 };
+            let mut left = x;
+            for &item in row {
+                let size = if band > 0.0 { item / band } else { 0.0 };
+                placed.push((left, y, size, band));
+                left += size;
+            }
+            free = (x, y + band, w, h - band);
+        }
+        start = end;
+    }
+
+    // Round edges rather than sizes so neighbours share them without gaps
+    placed
+        .into_iter()
+        .map(|(x, y, w, h)| {
+            let left = x.round() as u16;
+            let right = (x + w).round() as u16;
+            let top = (y / 2.0).round() as u16;
+            let bottom = ((y + h) / 2.0).round() as u16;
+            let (width, height) = (right.saturating_sub(left), bottom.saturating_sub(top));
+            Rect::new(area.x + left, area.y + top, width, height).intersection(area)
+        })
+        .collect()
+}
+
+/// The worst aspect ratio of a row of areas laid along a side of length `side`
+fn worst(row: &[f64], side: f64) -> f64 {
+    let sum: f64 = row.iter().sum();
+    let (min, max) = row.iter().fold((f64::MAX, 0.0f64), |(min, max), &area| {
+        (min.min(area), max.max(area))
+    });
+    if sum <= 0.0 || min <= 0.0 {
+        return f64::MAX;
+    }
+    let side = side * side;
+    (side * max / (sum * sum)).max(sum * sum / (side * min))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::squarify;
+    use ratatui::layout::Rect;
+
+    /// How many rectangles cover each cell of `area`, row by row
+    fn coverage(rects: &[Rect], area: Rect) -> Vec<usize> {
+        let mut cells = vec![0; area.area() as usize];
+        for rect in rects {
+            assert_eq!(rect.intersection(area), *rect, "{rect:?} leaves {area:?}");
+            for y in rect.top()..rect.bottom() {
+                for x in rect.left()..rect.right() {
+                    cells[((y - area.y) * area.width + x - area.x) as usize] += 1;
+                }
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn tiles_the_whole_area() {
+        let area = Rect::new(3, 2, 57, 19);
+        for sizes in [
+            vec![100],
+            vec![50, 30, 20],
+            vec![500, 120, 80, 80, 33, 20, 7, 5, 1],
+            vec![6, 6, 6, 6, 6, 6],
+        ] {
+            let rects = squarify(&sizes, area);
+            assert_eq!(rects.len(), sizes.len());
+            assert!(coverage(&rects, area).iter().all(|&n| n == 1), "{sizes:?}");
+        }
+    }
+
+    #[test]
+    fn larger_sizes_get_more_room() {
+        let rects = squarify(&[300, 200, 100], Rect::new(0, 0, 60, 20));
+        let areas: Vec<u16> = rects.iter().map(|rect| rect.area()).collect();
+        assert!(areas[0] > areas[1] && areas[1] > areas[2], "{areas:?}");
+    }
+
+    #[test]
+    fn zero_sizes_get_empty_rectangles() {
+        let area = Rect::new(0, 0, 40, 10);
+        let rects = squarify(&[10, 5, 0, 0], area);
+        assert!(rects[2].is_empty() && rects[3].is_empty());
+        assert!(coverage(&rects, area).iter().all(|&n| n == 1));
+
+        assert_eq!(squarify(&[0, 0], area), vec![Rect::default(); 2]);
+        assert!(squarify(&[], area).is_empty());
+        assert_eq!(squarify(&[5], Rect::new(4, 4, 0, 3)), vec![Rect::default()]);
+    }
+}
@@ -0,0 +1,1323 @@
+use crate::app::{App, View, BREADCRUMB_SEPARATOR};
+use crate::content::{self, Body, FileContent};
+use crate::highlight::{LineTokens, Token};
+use crate::timeline::{self, TimeField};
+use crate::treemap::{self, ColorBy, Kind};
+use chrono::{DateTime, Local};
+use planter::{FileNode, LinkTarget, TimelineSource};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    symbols::Marker,
+    widgets::{
+        canvas::{Canvas, Points},
+        Block, BorderType, Borders, Clear, List, ListItem, Paragraph,
+    },
+    Frame,
+};
+use std::{collections::HashMap, time::SystemTime};
+
+// Icon constants
+const ICON_ROOT: &str = ""; // nf-fa-seedling
+const ICON_FOLDER: &str = ""; // nf-fa-folder
+const ICON_FILE: &str = ""; // file icon
+const ICON_TREE_COMPLETE: &str = ""; // nf-fa-tree
+const ICON_SPINNER: &str = ""; // nf-fa-spinner
+const ICON_WARNING: &str = "⚠"; // Marks folders with scan errors
+const ICON_MOUNT: &str = "⏏"; // Marks folders on a different filesystem than their parent
+
+// Bar graph constants
+const SIZE_BAR_WIDTH: usize = 10; // Width of the share-of-parent bar in the tree
+const PARTIAL_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉']; // 1/8 to 7/8 of a cell
+
+/// Where the three panels go; also used to route mouse events
+pub struct Panes {
+    pub tree: Rect,
+    pub stats: Rect,
+    pub preview: Rect,
+}
+
+impl Panes {
+    pub fn new(area: Rect) -> Self {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+            .margin(0)
+            .split(area);
+        // Right panel: Split into stats and preview
+        let right_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .margin(0)
+            .split(chunks[1]);
+        Panes {
+            tree: chunks[0],
+            stats: right_chunks[0],
+            preview: right_chunks[1],
+        }
+    }
+}
+
+pub fn ui(f: &mut Frame, app: &mut App) {
+    // Rendering reads the visible rows from the cache, so make sure it is current
+    let _ = app.get_visible_node_indices();
+    let panes = Panes::new(f.area());
+    app.layout_chart(panes.tree);
+    let app = &*app;
+
+    // Left panel: Tree view, or a chart in its place
+    match app.view {
+        View::Tree => render_tree(f, app, panes.tree),
+        View::Treemap => render_treemap(f, app, panes.tree),
+        View::Sunburst => render_sunburst(f, app, panes.tree),
+    }
+
+    // Top right: Statistics
+    render_stats(f, app, panes.stats);
+
+    // Bottom right: Folder contents preview
+    render_preview(f, app, panes.preview);
+
+    // Ctrl-P finder floats above everything else
+    if app.finder.is_some() {
+        render_finder(f, app, f.area());
+    }
+    if app.errors_view.is_some() {
+        render_errors(f, app, f.area());
+    }
+}
+
+/// A rectangle of the given percentage size centered in `area`
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+fn render_finder(f: &mut Frame, app: &App, area: Rect) {
+    let Some(finder) = &app.finder else {
+        return;
+    };
+    let popup = centered_rect(80, 70, area);
+    f.render_widget(Clear, popup);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} Jump to folder ", ICON_FOLDER))
+        .title_bottom(" ↑/↓ - Move  Enter - Jump  Esc - Close ")
+        .style(Style::default().fg(app.theme.border));
+    let inner = block.inner(popup);
+    f.render_widget(block, popup);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(rows[1]);
+
+    // Query line with the number of matches
+    let input = Line::from(vec![
+        Span::styled(" > ", Style::default().fg(app.theme.highlight)),
+        Span::styled(
+            format!("{}█", finder.query),
+            Style::default().fg(app.theme.text).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!("  {}/{}", finder.hits.len(), finder.paths.len()),
+            Style::default().fg(app.theme.muted),
+        ),
+    ]);
+    f.render_widget(Paragraph::new(input), rows[0]);
+
+    // Ranked results, scrolled to keep the highlighted one in view
+    let height = columns[0].height as usize;
+    let offset = finder.selected.saturating_sub(height.saturating_sub(1));
+    let items: Vec<ListItem> = finder
+        .hits
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(pos, hit)| {
+            let selected = pos == finder.selected;
+            let base = if selected {
+                Style::default().fg(app.theme.folder).bg(app.theme.selection)
+            } else {
+                Style::default().fg(app.theme.folder)
+            };
+            let matched = base.fg(app.theme.highlight).add_modifier(Modifier::BOLD);
+            let mut spans = vec![Span::styled(if selected { " ▶ " } else { "   " }, base)];
+            spans.extend(finder.paths[hit.node].chars().enumerate().map(|(i, c)| {
+                let style = if hit.positions.contains(&i) { matched } else { base };
+                Span::styled(c.to_string(), style)
+            }));
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    f.render_widget(List::new(items), columns[0]);
+
+    // Details of the highlighted entry, taken from the scan
+    let mut preview = Vec::new();
+    if let Some(idx) = finder.selected_node() {
+        let node = &app.tree.nodes[idx];
+        preview.push(Line::from(Span::styled(
+            format!("{} {}", node_icon(node), node.name),
+            Style::default().fg(app.theme.folder).add_modifier(Modifier::BOLD),
+        )));
+        preview.push(Line::from(Span::styled(
+            node.path.to_string_lossy().to_string(),
+            Style::default().fg(app.theme.muted),
+        )));
+        let mut details = vec![Span::styled(
+            humansize::format_size(node.usage(app.apparent_size), humansize::BINARY),
+            Style::default().fg(app.theme.highlight),
+        )];
+        if node.is_dir {
+            details.push(Span::styled(
+                format!("  {} files  {} dirs", node.file_count, node.dir_count),
+                Style::default().fg(app.theme.muted),
+            ));
+        }
+        preview.push(Line::from(details));
+        preview.push(Line::from(""));
+        // Children directly follow their parent in depth-first order
+        preview.extend(
+            app.tree.nodes[idx + 1..]
+                .iter()
+                .take_while(|child| child.depth > node.depth)
+                .filter(|child| child.depth == node.depth + 1)
+                .filter(|child| child.is_dir || app.show_files)
+                .take(columns[1].height as usize)
+                .map(|child| {
+                    Line::from(Span::styled(
+                        format!(" {} {}", node_icon(child), child.name),
+                        Style::default().fg(app.theme.text),
+                    ))
+                }),
+        );
+    }
+    f.render_widget(
+        Paragraph::new(preview).block(Block::default().borders(Borders::LEFT)),
+        columns[1],
+    );
+}
+
+/// Overlay listing every path the scan could not read
+fn render_errors(f: &mut Frame, app: &App, area: Rect) {
+    let Some(selected) = app.errors_view else {
+        return;
+    };
+    let popup = centered_rect(80, 70, area);
+    f.render_widget(Clear, popup);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} Scan errors ({}) ", ICON_WARNING, app.tree.errors.len()))
+        .title_bottom(" ↑/↓ - Move  Enter - Jump to folder  Esc - Close ")
+        .style(Style::default().fg(app.theme.border));
+    let inner = block.inner(popup);
+    f.render_widget(block, popup);
+
+    // Keep the highlighted error in view
+    let height = inner.height as usize;
+    let offset = selected.saturating_sub(height.saturating_sub(1));
+    let kind_width = app
+        .tree
+        .errors
+        .iter()
+        .map(|error| format!("{:?}", error.kind).len())
+        .max()
+        .unwrap_or(0);
+    let items: Vec<ListItem> = app
+        .tree
+        .errors
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(pos, error)| {
+            let mut style = Style::default().fg(app.theme.text);
+            if pos == selected {
+                style = style.bg(app.theme.selection);
+            }
+            ListItem::new(Line::from(vec![
+                Span::styled(if pos == selected { " ▶ " } else { "   " }, style),
+                Span::styled(
+                    format!("{:<width$}  ", format!("{:?}", error.kind), width = kind_width),
+                    style.fg(app.theme.error),
+                ),
+                Span::styled(error.path.to_string_lossy().to_string(), style),
+            ]))
+        })
+        .collect();
+    f.render_widget(List::new(items), inner);
+}
+
+fn render_tree(f: &mut Frame, app: &App, area: Rect) {
+    let visible_height = area.height.saturating_sub(2) as usize; // Account for borders
+    let inner_width = area.width.saturating_sub(2) as usize;
+
+    // First, collect all visible nodes with their index in the full list
+    let all_visible: Vec<(usize, &FileNode)> = app
+        .visible_node_indices_cache
+        .iter()
+        .map(|&idx| (idx, &app.tree.nodes[idx]))
+        .collect();
+
+    let visible_nodes: Vec<ListItem> = all_visible
+        .iter()
+        .enumerate()
+        .skip(app.scroll_offset)
+        .take(visible_height)
+        .map(|(_list_idx, (actual_index, node))| {
+            // Build tree connectors
+            let mut tree_prefix = String::new();
+
+            if node.depth > 0 {
+                let indent = tree_indent(&app.tree.nodes, *actual_index, app.show_files);
+                tree_prefix.push_str(&indent);
+
+                // Determine connector for current node
+                let is_last = is_last(node, app.show_files);
+                let base_connector = if is_last {
+                    "└─" // Last child uses corner
+                } else {
+                    "├─" // Not last child uses tee
+                };
+
+                // Animation effect: show growing roots
+                if !app.animation_complete && node.depth == app.animation_depth {
+                    let prefix = if is_last { "└" } else { "├" };
+                    match app.animation_frame % 3 {
+                        0 => tree_prefix.push_str(prefix),
+                        1 => tree_prefix.push_str(&format!("{}─", prefix)),
+                        _ => tree_prefix.push_str(base_connector),
+                    }
+                } else {
+                    tree_prefix.push_str(base_connector);
+                }
+            }
+
+            let icon = node_icon(node);
+
+            let display_name = if node.name.is_empty() {
+                node.path.to_string_lossy().to_string()
+            } else {
+                node.name.clone()
+            };
+
+            let mut style = if node.is_dir {
+                Style::default()
+                    .fg(app.theme.folder)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(app.theme.text)
+            };
+
+            if app.selected_index == Some(*actual_index) {
+                style = style.bg(app.theme.selection);
+            }
+
+            // Color the tree connectors differently
+            let connector_style = Style::default().fg(app.theme.border);
+            let icon_style = style;
+
+            let mut spans = vec![
+                Span::styled(tree_prefix, connector_style),
+                Span::styled(format!("{} ", icon), icon_style),
+            ];
+            // Highlight the part of the name that matches the search
+            match app.search.find_in(&node.name) {
+                Some(range) if !node.name.is_empty() => {
+                    let match_style = icon_style.fg(app.theme.match_text).bg(app.theme.highlight);
+                    spans.push(Span::styled(node.name[..range.start].to_string(), icon_style));
+                    spans.push(Span::styled(node.name[range.clone()].to_string(), match_style));
+                    spans.push(Span::styled(node.name[range.end..].to_string(), icon_style));
+                }
+                _ => spans.push(Span::styled(display_name, icon_style)),
+            }
+            if let Some(link) = &node.link {
+                spans.push(Span::styled(link_label(link), icon_style.fg(app.theme.muted)));
+                if link.broken {
+                    spans.push(Span::styled(" (broken)", icon_style.fg(app.theme.error)));
+                }
+            }
+            if node.error.is_some() {
+                spans.push(Span::styled(
+                    format!(" {}", ICON_WARNING),
+                    Style::default().fg(app.theme.error),
+                ));
+            }
+            if app.is_collapsed(*actual_index) {
+                let folded = node.dir_count + if app.show_files { node.file_count } else { 0 };
+                spans.push(Span::styled(
+                    format!(" ▸ {} folded", folded),
+                    Style::default().fg(app.theme.muted),
+                ));
+            }
+
+            // Right-align the folder totals, dropping the counts first when space runs out
+            let used: usize = spans.iter().map(|s| s.width()).sum();
+            let totals = [size_columns(app, node, true), size_columns(app, node, false)]
+                .into_iter()
+                .find(|cols| used + 1 + cols.iter().map(|s| s.width()).sum::<usize>() <= inner_width);
+            if let Some(cols) = totals {
+                let cols_width: usize = cols.iter().map(|s| s.width()).sum();
+                spans.push(Span::raw(" ".repeat(inner_width - used - cols_width)));
+                spans.extend(cols);
+            }
+
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let visible_count = all_visible.len();
+    let title = format!(
+        " {} ({}/{}) - Depth {}/{} - Sort: {} {} ",
+        if app.animation_complete {
+            ICON_TREE_COMPLETE
+        } else {
+            ICON_SPINNER
+        },
+        visible_count,
+        if app.show_files {
+            app.tree.nodes.len()
+        } else {
+            app.tree.stats.total_dirs
+        },
+        app.animation_depth,
+        app.tree.stats.max_depth,
+        app.sort.key.label(),
+        if app.sort.descending { "↓" } else { "↑" }
+    );
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .style(Style::default().fg(app.theme.border));
+    if let Some(prompt) = search_prompt(app) {
+        block = block.title_bottom(prompt);
+    }
+
+    let list = List::new(visible_nodes).block(block);
+
+    f.render_widget(list, area);
+}
+
+/// The search query and result count, shown in the tree's bottom border while a search is open
+fn search_prompt(app: &App) -> Option<Line<'static>> {
+    let search = &app.search;
+    if !search.editing && !search.is_active() {
+        return None;
+    }
+
+    let prompt = if search.filter { "&" } else { "/" };
+    let cursor = if search.editing { "█" } else { "" };
+    let mut spans = vec![
+        Span::styled(
+            format!(" {}{}{} ", prompt, search.query, cursor),
+            Style::default().fg(app.theme.text).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!("[{}] ", search.mode.label()),
+            Style::default().fg(app.theme.muted),
+        ),
+    ];
+    if let Some(error) = &search.error {
+        spans.push(Span::styled(
+            format!("{} ", error.lines().last().unwrap_or_default()),
+            Style::default().fg(app.theme.error),
+        ));
+    } else if search.is_active() {
+        let position = app
+            .selected_index
+            .and_then(|idx| search.matches.iter().position(|&m| m == idx))
+            .map_or("-".to_string(), |pos| (pos + 1).to_string());
+        spans.push(Span::styled(
+            format!("{}/{} matches ", position, search.matches.len()),
+            Style::default().fg(app.theme.highlight),
+        ));
+    }
+    Some(Line::from(spans))
+}
+
+/// The arrow and target shown after a symbolic link's name
+pub fn link_label(link: &LinkTarget) -> String {
+    format!(" -> {}", link.path.display())
+}
+
+/// Icon shown before a node's name in the tree
+pub fn node_icon(node: &FileNode) -> &'static str {
+    // Use Nerd Font icons instead of emojis
+    if !node.is_dir {
+        ICON_FILE
+    } else if node.depth == 0 {
+        ICON_ROOT
+    } else if node.mount_point {
+        ICON_MOUNT
+    } else {
+        ICON_FOLDER
+    }
+}
+
+/// Whether a node is drawn with `└─`, i.e. no sibling is shown below it
+pub fn is_last(node: &FileNode, files: bool) -> bool {
+    if files {
+        node.is_last_entry
+    } else {
+        node.is_last_child
+    }
+}
+
+/// The connector columns drawn before a node's own `├─`/`└─`.
+///
+/// Column 0 carries the line down from the root, so it follows the node's
+/// depth-1 ancestor (the node itself at depth 1); every further column shows
+/// a vertical line while the ancestor at that depth has siblings below it,
+/// counting files as siblings if they are shown.
+pub fn tree_indent(nodes: &[FileNode], idx: usize, files: bool) -> String {
+    let depth = nodes[idx].depth;
+    // The ancestor at each depth from 1 to the node's own
+    let mut ancestors = vec![idx; depth];
+    let mut current = idx;
+    for slot in ancestors.iter_mut().rev() {
+        *slot = current;
+        current = nodes[current].parent.unwrap_or(current);
+    }
+
+    let mut indent = String::new();
+    for column in 0..depth {
+        let ancestor = ancestors[column.saturating_sub(1)];
+        if is_last(&nodes[ancestor], files) {
+            // Two spaces keep the alignment when no vertical line is needed
+            indent.push_str("  ");
+        } else {
+            indent.push_str("│ ");
+        }
+    }
+    indent
+}
+
+/// A total size in the Info panel; the one the tree shows is highlighted
+fn size_line<'a>(app: &App, label: &'a str, size: u64, shown: bool) -> Line<'a> {
+    let color = if shown {
+        app.theme.highlight
+    } else {
+        app.theme.muted
+    };
+    Line::from(vec![
+        Span::styled(label, Style::default().add_modifier(Modifier::BOLD)),
+        Span::styled(
+            humansize::format_size(size, humansize::BINARY),
+            Style::default().fg(color),
+        ),
+    ])
+}
+
+/// Build the right-hand columns for a folder: optional counts, cumulative size,
+/// and a bar showing its share of the parent folder's size (or of all roots, for a root).
+fn size_columns<'a>(app: &App, node: &FileNode, with_counts: bool) -> Vec<Span<'a>> {
+    let parent_size = node
+        .parent
+        .and_then(|idx| app.tree.nodes.get(idx))
+        .map_or(app.tree.stats.usage(app.apparent_size), |parent| {
+            parent.usage(app.apparent_size)
+        });
+    let size = node.usage(app.apparent_size);
+    let fraction = if parent_size == 0 {
+        0.0
+    } else {
+        size as f64 / parent_size as f64
+    };
+
+    let mut cols = Vec::new();
+    if with_counts && node.is_dir {
+        cols.push(Span::styled(
+            format!("{} files {} dirs ", node.file_count, node.dir_count),
+            Style::default().fg(app.theme.muted),
+        ));
+    }
+    cols.push(Span::styled(
+        format!("{:>10} ", humansize::format_size(size, humansize::BINARY)),
+        Style::default().fg(app.theme.highlight),
+    ));
+    cols.push(Span::styled(
+        format!("[{}]", size_bar(fraction, SIZE_BAR_WIDTH)),
+        Style::default().fg(app.theme.border),
+    ));
+    cols
+}
+
+/// Draw `fraction` (0.0 to 1.0) as a bar of `width` cells with 1/8 cell precision.
+fn size_bar(fraction: f64, width: usize) -> String {
+    let eighths = (fraction.clamp(0.0, 1.0) * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if let Some(&partial) = (eighths % 8).checked_sub(1).and_then(|i| PARTIAL_BLOCKS.get(i)) {
+        bar.push(partial);
+    }
+    let filled = bar.chars().count();
+    bar.push_str(&" ".repeat(width - filled));
+    bar
+}
+
+/// Format a SystemTime as a date string with a label.
+///
+/// # Arguments
+/// * `time` - The SystemTime to format
+/// * `label` - A label to append to the formatted date (e.g., "newest", "oldest")
+///
+/// # Returns
+/// Returns Some(String) containing the formatted date with label.
+/// Currently always returns Some, but uses Option for future error handling flexibility.
+pub fn format_system_time(time: SystemTime, label: &str) -> Option<String> {
+    let datetime: DateTime<Local> = time.into();
+    Some(format!(" {} ({})", datetime.format("%Y-%m-%d"), label))
+}
+
+fn render_stats(f: &mut Frame, app: &App, area: Rect) {
+    let mut stats_text = vec![
+        Line::from(vec![Span::styled(
+            " Statistics",
+            Style::default()
+                .fg(app.theme.highlight)
+                .add_modifier(Modifier::BOLD),
+        )]),
+        Line::from(vec![
+            Span::styled(" Folders: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(
+                format!("{}", app.tree.stats.total_dirs),
+                Style::default().fg(app.theme.folder),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(" Files: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(
+                format!("{}", app.tree.stats.total_files),
+                Style::default().fg(app.theme.border),
+            ),
+        ]),
+        size_line(app, " Disk Usage: ", app.tree.stats.total_disk_size, !app.apparent_size),
+        size_line(app, " Apparent Size: ", app.tree.stats.total_size, app.apparent_size),
+        Line::from(vec![
+            Span::styled(
+                " Max Depth: ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("{}", app.tree.stats.max_depth),
+                Style::default().fg(app.theme.accent),
+            ),
+        ]),
+    ];
+
+    if !app.tree.errors.is_empty() {
+        stats_text.push(Line::from(vec![
+            Span::styled(
+                format!(" {} Errors: ", ICON_WARNING),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("{} (e - show)", app.tree.errors.len()),
+                Style::default().fg(app.theme.error),
+            ),
+        ]));
+    }
+
+    // Mount points the scan did not cross, a few by name
+    const LISTED_FILESYSTEMS: usize = 3;
+    let filesystems = &app.tree.skipped_filesystems;
+    if !filesystems.is_empty() {
+        stats_text.push(Line::from(vec![
+            Span::styled(
+                format!(" {} Other filesystems: ", ICON_MOUNT),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("{} skipped", filesystems.len()),
+                Style::default().fg(app.theme.muted),
+            ),
+        ]));
+        for path in filesystems.iter().take(LISTED_FILESYSTEMS) {
+            stats_text.push(Line::from(Span::styled(
+                format!("   {}", path.display()),
+                Style::default().fg(app.theme.muted),
+            )));
+        }
+        if filesystems.len() > LISTED_FILESYSTEMS {
+            stats_text.push(Line::from(Span::styled(
+                format!("   and {} more", filesystems.len() - LISTED_FILESYSTEMS),
+                Style::default().fg(app.theme.muted),
+            )));
+        }
+    }
+
+    if app.tree.stats.hard_links > 0 {
+        stats_text.push(Line::from(vec![
+            Span::styled(" Hard links: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(
+                format!("{} counted once", app.tree.stats.hard_links),
+                Style::default().fg(app.theme.muted),
+            ),
+        ]));
+    }
+
+    if app.tree.stats.broken_links > 0 {
+        stats_text.push(Line::from(vec![
+            Span::styled(" Broken links: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(
+                format!("{}", app.tree.stats.broken_links),
+                Style::default().fg(app.theme.error),
+            ),
+        ]));
+    }
+
+    if app.tree.stats.skipped_entries > 0 {
+        stats_text.push(Line::from(vec![
+            Span::styled(" Skipped: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(
+                format!("{}", app.tree.stats.skipped_entries),
+                Style::default().fg(app.theme.muted),
+            ),
+        ]));
+    }
+
+    if app.scanning {
+        stats_text.push(Line::from(vec![Span::styled(
+            format!(" {} Scanning...", ICON_SPINNER),
+            Style::default().fg(app.theme.highlight),
+        )]));
+    }
+
+    timeline_lines(app, area, &mut stats_text);
+
+    stats_text.extend(vec![
+        Line::from(vec![Span::styled(
+            " Controls:",
+            Style::default()
+                .fg(app.theme.text)
+                .add_modifier(Modifier::BOLD),
+        )]),
+        Line::from(vec![Span::raw(" ↑/↓ - Navigate selection")]),
+        Line::from(vec![Span::raw(" ←/→/Space - Fold/unfold")]),
+        Line::from(vec![Span::raw(" * - Unfold all below")]),
+        Line::from(vec![Span::raw(" 0-9 - Fold to depth")]),
+        Line::from(vec![Span::raw(" . - Show/hide hidden")]),
+        Line::from(vec![Span::raw(" f - Show/hide files")]),
+        Line::from(vec![Span::raw(" a - Apparent size/disk usage")]),
+        Line::from(vec![Span::raw(" s - Sort by, r - Reverse")]),
+        Line::from(vec![Span::raw(" [/] - Scroll preview")]),
+        Line::from(vec![Span::raw(" Tab - Tree/preview focus")]),
+        Line::from(vec![Span::raw(" v - Tree/treemap/sunburst")]),
+        Line::from(vec![Span::raw(" t - Timeline focus")]),
+        Line::from(vec![Span::raw(" / - Search, n/N - Next/prev")]),
+        Line::from(vec![Span::raw(" & - Filter matches")]),
+        Line::from(vec![Span::raw(" Ctrl-P - Jump to folder")]),
+        Line::from(vec![Span::raw(" Wheel - Scroll panels")]),
+        if app.animation_complete {
+            Line::from(vec![Span::styled(
+                " Click - Select/Open",
+                Style::default().fg(app.theme.border),
+            )])
+        } else {
+            Line::from(vec![Span::styled(
+                " Wait for animation...",
+                Style::default().fg(app.theme.muted),
+            )])
+        },
+        Line::from(vec![Span::raw(" Q/Esc - Quit")]),
+    ]);
+
+    // The border shows when the timeline has the keyboard
+    let border = if app.timeline_focused {
+        app.theme.highlight
+    } else {
+        app.theme.border
+    };
+    let paragraph = Paragraph::new(stats_text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("  Info ")
+            .style(Style::default().fg(border)),
+    );
+
+    f.render_widget(paragraph, area);
+}
+
+/// The file timeline: a bar per bucket around the cursor, then the filter and hints
+fn timeline_lines(app: &App, area: Rect, lines: &mut Vec<Line<'static>>) {
+    let timeline = &app.timeline;
+    if timeline.counts.is_empty() && timeline.undated == 0 {
+        return;
+    }
+    lines.push(Line::from(vec![
+        Span::styled(
+            " File Timeline: ",
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!(
+                "{}, by {}",
+                timeline.field_label(),
+                timeline.granularity.label()
+            ),
+            Style::default().fg(app.theme.muted),
+        ),
+    ]));
+
+    let shown = timeline.scroll..(timeline.scroll + timeline::ROWS).min(timeline.counts.len());
+    let labels: Vec<String> = shown.clone().map(|bucket| timeline.label(bucket)).collect();
+    let max_count = timeline.counts[shown.clone()]
+        .iter()
+        .copied()
+        .max()
+        .unwrap_or(0);
+    let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+    let count_width = max_count.to_string().len();
+    // Borders, and a space before the label, the bar and the count
+    let bar_width = (area.width as usize)
+        .saturating_sub(label_width + count_width + 5)
+        .max(1);
+    let selection = timeline.selection();
+    let filter = timeline.filter.as_ref();
+    for (bucket, label) in shown.clone().zip(labels) {
+        let count = timeline.counts[bucket];
+        let bar_len = (count * bar_width).checked_div(max_count).unwrap_or(0);
+        let bar_len = if count > 0 { bar_len.max(1) } else { 0 };
+        let in_filter = filter.is_some_and(|filter| {
+            let period = timeline.period(bucket..bucket + 1);
+            period.start >= filter.start && period.end <= filter.end
+        });
+        let bar_color = if in_filter {
+            app.theme.highlight
+        } else {
+            app.theme.border
+        };
+        let mut style = Style::default();
+        if app.timeline_focused && selection.contains(&bucket) {
+            style = style.bg(app.theme.selection);
+        }
+        lines.push(
+            Line::from(vec![
+                Span::styled(
+                    format!(" {:<label_width$} ", label),
+                    Style::default().fg(app.theme.text),
+                ),
+                Span::styled("█".repeat(bar_len), Style::default().fg(bar_color)),
+                Span::styled(format!(" {}", count), Style::default().fg(app.theme.muted)),
+            ])
+            .style(style),
+        );
+    }
+
+    let mut notes = Vec::new();
+    if timeline.counts.len() > shown.len() {
+        notes.push(format!(
+            "{}-{} of {}",
+            shown.start + 1,
+            shown.end,
+            timeline.counts.len()
+        ));
+    }
+    if timeline.zoom.is_some() {
+        notes.push("zoomed".to_string());
+    }
+    if timeline.field == TimeField::Created && timeline.source == TimelineSource::Modified {
+        notes.push("no creation times recorded".to_string());
+    }
+    if timeline.undated > 0 {
+        notes.push(format!(
+            "{} without a {} time",
+            timeline.undated,
+            timeline.field_label()
+        ));
+    }
+    if !notes.is_empty() {
+        lines.push(Line::from(Span::styled(
+            format!(" {}", notes.join(", ")),
+            Style::default().fg(app.theme.muted),
+        )));
+    }
+    if let Some(filter) = filter {
+        let last = filter.end.pred_opt().unwrap_or(filter.end);
+        lines.push(Line::from(vec![
+            Span::styled(" Filter: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(
+                format!(
+                    "{} to {}",
+                    filter.start.format("%Y-%m-%d"),
+                    last.format("%Y-%m-%d")
+                ),
+                Style::default().fg(app.theme.highlight),
+            ),
+        ]));
+    }
+    if app.timeline_focused {
+        for hint in [
+            " Shift-↑/↓ - Pick a range",
+            " Enter - Filter, +/- - Zoom",
+            " g - Period, c - Timestamp",
+        ] {
+            lines.push(Line::from(Span::styled(
+                hint,
+                Style::default().fg(app.theme.muted),
+            )));
+        }
+    }
+}
+
+fn render_preview(f: &mut Frame, app: &App, area: Rect) {
+    // The border shows which pane has the keyboard
+    let border = if app.preview_focused {
+        app.theme.highlight
+    } else {
+        app.theme.border
+    };
+    if let Some(file) = &app.preview_file {
+        render_file_preview(f, app, file, border, area);
+        return;
+    }
+    let visible_height = area.height.saturating_sub(2) as usize;
+
+    let mut preview_items: Vec<ListItem> = app
+        .preview_contents
+        .iter()
+        .enumerate()
+        .skip(app.preview_scroll_offset)
+        .take(visible_height)
+        .map(|(pos, item)| {
+            let icon = if item.is_dir { ICON_FOLDER } else { ICON_FILE };
+
+            let size_str = if item.is_dir {
+                String::new()
+            } else {
+                format!(
+                    " ({})",
+                    humansize::format_size(item.size, humansize::BINARY)
+                )
+            };
+
+            let style = if item.is_dir {
+                Style::default()
+                    .fg(app.theme.folder)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(app.theme.text)
+            };
+
+            let mut spans = vec![Span::styled(format!(" {} {}", icon, item.name), style)];
+            if let Some(link) = &item.link {
+                spans.push(Span::styled(link_label(link), Style::default().fg(app.theme.muted)));
+                if link.broken {
+                    spans.push(Span::styled(" (broken)", Style::default().fg(app.theme.error)));
+                }
+            }
+            spans.push(Span::styled(size_str, style));
+            let line = Line::from(spans);
+
+            if app.preview_focused && pos == app.preview_selected {
+                ListItem::new(line).style(Style::default().bg(app.theme.selection))
+            } else {
+                ListItem::new(line)
+            }
+        })
+        .collect();
+
+    if let Some(kind) = app.preview_error {
+        preview_items.insert(
+            0,
+            ListItem::new(Line::from(Span::styled(
+                format!(" {} Cannot list folder: {:?}", ICON_WARNING, kind),
+                Style::default().fg(app.theme.error),
+            ))),
+        );
+    }
+
+    let title = if let Some(idx) = app.selected_index {
+        if let Some(node) = app.tree.nodes.get(idx) {
+            format!(
+                " {} {} ({} items) ",
+                ICON_FOLDER,
+                node.name,
+                app.preview_contents.len()
+            )
+        } else {
+            format!(" {} Folder Contents ", ICON_FOLDER)
+        }
+    } else {
+        format!(" {} Folder Contents (Click to select) ", ICON_FOLDER)
+    };
+
+    let list = List::new(preview_items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(border)),
+    );
+
+    f.render_widget(list, area);
+}
+
+/// The first lines of the selected file, or a hex dump when it is binary
+fn render_file_preview(f: &mut Frame, app: &App, file: &FileContent, border: Color, area: Rect) {
+    let visible_height = area.height.saturating_sub(2) as usize;
+    let row_len = content::hex_row_len(area.width.saturating_sub(2) as usize);
+    let text_style = Style::default().fg(app.theme.text);
+    let muted = Style::default().fg(app.theme.muted);
+    let error = Style::default().fg(app.theme.error);
+
+    let rows = file.line_count(row_len);
+    let offset = app.preview_scroll_offset.min(rows.saturating_sub(1));
+    let (kind, mut lines): (String, Vec<Line>) = match &file.body {
+        Body::Text {
+            lines,
+            encoding,
+            syntax,
+        } => (
+            match syntax {
+                Some(syntax) => format!("{}, {}", syntax.language, encoding.label()),
+                None => encoding.label().to_string(),
+            },
+            lines
+                .iter()
+                .enumerate()
+                .skip(offset)
+                .take(visible_height)
+                .map(|(idx, line)| match syntax {
+                    Some(syntax) => highlighted_line(app, line, &syntax.lines[idx]),
+                    None => Line::styled(format!(" {}", line), text_style),
+                })
+                .collect(),
+        ),
+        Body::Binary(bytes) => (
+            "binary".to_string(),
+            bytes
+                .chunks(row_len)
+                .enumerate()
+                .skip(offset)
+                .take(visible_height)
+                .map(|(row, chunk)| {
+                    let dump = content::hex_row(row * row_len, chunk, row_len);
+                    Line::styled(format!(" {}", dump), muted)
+                })
+                .collect(),
+        ),
+        Body::Unreadable(kind) => (
+            "unreadable".to_string(),
+            vec![Line::styled(
+                format!(" {} Cannot read file: {:?}", ICON_WARNING, kind),
+                error,
+            )],
+        ),
+        Body::Special => (
+            "special".to_string(),
+            vec![Line::styled(" Not a regular file", muted)],
+        ),
+    };
+    // Mark the cut once the end of what was read is on screen
+    if file.truncated && offset + visible_height >= rows && lines.len() < visible_height {
+        lines.push(Line::styled(" … truncated", muted));
+    }
+
+    let title = format!(
+        " {} {} ({}, {}) ",
+        ICON_FILE,
+        file.name(),
+        kind,
+        humansize::format_size(file.size, humansize::BINARY)
+    );
+    let preview = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(border)),
+    );
+    f.render_widget(preview, area);
+}
+
+/// A line of source split into spans colored by the theme
+fn highlighted_line<'a>(app: &App, line: &'a str, tokens: &LineTokens) -> Line<'a> {
+    let plain = Style::default().fg(app.theme.text);
+    let mut spans = vec![Span::raw(" ")];
+    let mut start = 0;
+    for (range, token) in tokens {
+        if range.start > start {
+            spans.push(Span::styled(&line[start..range.start], plain));
+        }
+        let style = match token {
+            Token::Comment => Style::default().fg(app.theme.muted),
+            Token::String => Style::default().fg(app.theme.string),
+            Token::Constant => Style::default().fg(app.theme.constant),
+            Token::Keyword => Style::default()
+                .fg(app.theme.keyword)
+                .add_modifier(Modifier::BOLD),
+            Token::Function => Style::default().fg(app.theme.function),
+            Token::Type => Style::default().fg(app.theme.type_name),
+        };
+        spans.push(Span::styled(&line[range.clone()], style));
+        start = range.end;
+    }
+    if start < line.len() {
+        spans.push(Span::styled(&line[start..], plain));
+    }
+    Line::from(spans)
+}
+
+/// The view name followed by the folders down to the charted one
+fn chart_title(app: &App) -> Line<'static> {
+    let mut title = vec![Span::raw(app.view.title())];
+    for (pos, (name, _)) in app.chart_breadcrumb().into_iter().enumerate() {
+        if pos > 0 {
+            title.push(Span::styled(
+                BREADCRUMB_SEPARATOR,
+                Style::default().fg(app.theme.muted),
+            ));
+        }
+        title.push(Span::styled(name, Style::default().fg(app.theme.folder)));
+    }
+    title.push(Span::raw(" "));
+    Line::from(title)
+}
+
+/// Tiles for the children of the charted folder, sized by usage
+fn render_treemap(f: &mut Frame, app: &App, area: Rect) {
+    let muted = Style::default().fg(app.theme.muted);
+
+    let shown: u64 = app
+        .treemap_tiles
+        .iter()
+        .map(|&(idx, _)| app.tree.nodes[idx].usage(app.apparent_size))
+        .sum();
+    let total = app.tree.stats.usage(app.apparent_size);
+    let share = (shown * 100).checked_div(total).unwrap_or(0);
+    let size = format!(
+        " {} ({}% of total) ",
+        humansize::format_size(shown, humansize::BINARY),
+        share
+    );
+
+    // Legend for the colors, with the key that switches them
+    let now = SystemTime::now();
+    let mut legend = vec![Span::raw(" ")];
+    let entries: &[(&str, Color)] = match app.treemap_color {
+        ColorBy::Kind => &[
+            ("folder", kind_color(app, Kind::Folder)),
+            ("code", kind_color(app, Kind::Code)),
+            ("document", kind_color(app, Kind::Document)),
+            ("media", kind_color(app, Kind::Media)),
+            ("archive", kind_color(app, Kind::Archive)),
+            ("other", kind_color(app, Kind::Other)),
+        ],
+        ColorBy::Age => &[
+            ("day", age_color(app, 0)),
+            ("month", age_color(app, 1)),
+            ("year", age_color(app, 2)),
+            ("older", age_color(app, 3)),
+        ],
+    };
+    for (label, color) in entries {
+        legend.push(Span::styled("■ ", Style::default().fg(*color)));
+        legend.push(Span::styled(format!("{} ", label), muted));
+    }
+    legend.push(Span::styled(
+        format!("(c: color by {}) ", app.treemap_color.next().label()),
+        muted,
+    ));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(chart_title(app))
+        .title(Line::from(size).right_aligned())
+        .title_bottom(Line::from(legend))
+        .style(Style::default().fg(app.theme.border));
+    f.render_widget(block, area);
+
+    if app.treemap_tiles.is_empty() {
+        let empty = Paragraph::new(Line::styled(" Nothing here takes up space", muted));
+        f.render_widget(empty, area.inner(ratatui::layout::Margin::new(1, 1)));
+        return;
+    }
+
+    let selected = app.chart_selected();
+    for &(idx, rect) in &app.treemap_tiles {
+        let node = &app.tree.nodes[idx];
+        let color = match app.treemap_color {
+            ColorBy::Kind => kind_color(app, Kind::of(node)),
+            ColorBy::Age => age_color(app, treemap::age_class(node, now)),
+        };
+        let is_selected = selected == Some(idx);
+        let fill = if is_selected {
+            Style::default().bg(app.theme.selection)
+        } else {
+            Style::default()
+        };
+        let icon = node_icon(node);
+        let size = humansize::format_size(node.usage(app.apparent_size), humansize::BINARY);
+
+        if rect.width >= 4 && rect.height >= 3 {
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_type(if is_selected {
+                    BorderType::Thick
+                } else {
+                    BorderType::Plain
+                })
+                .border_style(Style::default().fg(color))
+                .title(Span::styled(
+                    format!("{} {}", icon, node.name),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                ))
+                .style(fill);
+            let mut lines = vec![Line::styled(size, Style::default().fg(app.theme.highlight))];
+            if node.is_dir {
+                lines.push(Line::styled(
+                    format!("{} files {} dirs", node.file_count, node.dir_count),
+                    muted,
+                ));
+            }
+            f.render_widget(Paragraph::new(lines).block(block), rect);
+        } else {
+            // Too small for a border: the name if it fits, then shading
+            let width = rect.width as usize;
+            let name: String = node.name.chars().take(width).collect();
+            let mut lines = vec![Line::styled(
+                format!("{:░<width$}", name),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            )];
+            for _ in 1..rect.height {
+                lines.push(Line::styled("░".repeat(width), Style::default().fg(color)));
+            }
+            f.render_widget(Paragraph::new(lines).style(fill), rect);
+        }
+    }
+}
+
+fn kind_color(app: &App, kind: Kind) -> Color {
+    match kind {
+        Kind::Folder => app.theme.folder,
+        Kind::Code => app.theme.keyword,
+        Kind::Document => app.theme.string,
+        Kind::Media => app.theme.constant,
+        Kind::Archive => app.theme.type_name,
+        Kind::Other => app.theme.text,
+    }
+}
+
+/// Colors for `treemap::age_class`, from recent to old
+fn age_color(app: &App, class: usize) -> Color {
+    [
+        app.theme.highlight,
+        app.theme.string,
+        app.theme.accent,
+        app.theme.muted,
+    ][class.min(3)]
+}
+
+/// Rings of arcs around the charted folder, drawn in braille dots
+fn render_sunburst(f: &mut Frame, app: &App, area: Rect) {
+    let muted = Style::default().fg(app.theme.muted);
+    let sunburst = &app.sunburst;
+    let inner = app.sunburst_area;
+    let (width, height) = (inner.width as usize * 2, inner.height as usize * 4);
+    let selected = app.chart_selected();
+
+    // Arcs are colored by the branch they belong to, and the selected one stands out
+    let palette = [
+        app.theme.folder,
+        app.theme.keyword,
+        app.theme.string,
+        app.theme.constant,
+        app.theme.type_name,
+        app.theme.accent,
+    ];
+    let mut node_colors: HashMap<usize, Color> = HashMap::new();
+    let mut colors = Vec::with_capacity(sunburst.segments.len());
+    let mut branches = 0;
+    for segment in &sunburst.segments {
+        let color = if Some(segment.node) == selected {
+            app.theme.highlight
+        } else if segment.ring == 1 {
+            branches += 1;
+            palette[(branches - 1) % palette.len()]
+        } else {
+            // Parents come before their children
+            let parent = app.tree.nodes[segment.node].parent;
+            parent
+                .and_then(|parent| node_colors.get(&parent).copied())
+                .unwrap_or(app.theme.muted)
+        };
+        node_colors.insert(segment.node, color);
+        colors.push(color);
+    }
+    let center = if selected.is_some() && selected == app.chart_root_index() {
+        app.theme.highlight
+    } else {
+        app.theme.muted
+    };
+
+    // Each dot takes the color of the arc it falls in
+    let dots = &app.sunburst_dots;
+    let mut points: HashMap<Color, Vec<(f64, f64)>> = HashMap::new();
+    points.entry(center).or_default().extend(&dots.center);
+    for (seg, coords) in dots.segments.iter().enumerate() {
+        points.entry(colors[seg]).or_default().extend(coords);
+    }
+
+    // The arc under the mouse, or the selection, with its size
+    let shown = app.sunburst_hover.or(selected).or(app.chart_root_index());
+    let details = match shown.and_then(|idx| app.tree.nodes.get(idx)) {
+        Some(node) => {
+            let size = node.usage(app.apparent_size);
+            let total = app.tree.stats.usage(app.apparent_size);
+            let share = (size * 100).checked_div(total).unwrap_or(0);
+            format!(
+                " {} {} ({}% of total) ",
+                node.path.display(),
+                humansize::format_size(size, humansize::BINARY),
+                share
+            )
+        }
+        None => String::new(),
+    };
+
+    let canvas = Canvas::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(chart_title(app))
+                .title_bottom(Line::styled(details, Style::default().fg(app.theme.text)))
+                .style(Style::default().fg(app.theme.border)),
+        )
+        .marker(Marker::Braille)
+        .x_bounds([0.0, width.saturating_sub(1) as f64])
+        .y_bounds([0.0, height.saturating_sub(1) as f64])
+        .paint(move |ctx| {
+            for (color, coords) in &points {
+                ctx.draw(&Points {
+                    coords,
+                    color: *color,
+                });
+            }
+        });
+    f.render_widget(canvas, area);
+    if sunburst.segments.is_empty() {
+        let empty = Paragraph::new(Line::styled(" Nothing here takes up space", muted));
+        f.render_widget(empty, inner);
+    }
+}